@@ -51,18 +51,16 @@ fn main() {
         return;
     }
     let mut node_pool = NodePool::new();
-    let mut ast_pool = ASTPool::parse_src_pool(&mut node_pool, &src_pool, logger.clone());
+    let ast_pool = ASTPool::parse_src_pool(&mut node_pool, &src_pool, logger.clone());
 
     if args.debug_ast {
         for ast in &ast_pool {
-            println!("AST for {}", ast.get(&node_pool).span_or_builtin(&node_pool).0);
-            println!("{ast:#?}");
+            println!("AST for {}", ast.module().get(&node_pool).span_or_builtin(&node_pool).0);
+            println!("{:#?}", ast.module());
         }
     }
 
-    for ast in &mut ast_pool {
-        check_coherency(ast, &mut node_pool, logger.clone());
-    }
+    check_coherency(&ast_pool, &mut node_pool, logger.clone());
 
     let ref_logger = logger.lock().unwrap();
     println!(
@@ -1,4 +1,7 @@
 
+// darling-generated code for `#[darling(default)]` trips this lint
+#![allow(clippy::manual_unwrap_or_default)]
+
 extern crate proc_macro;
 extern crate proc_macro2;
 extern crate syn;
@@ -410,7 +413,7 @@ fn field_to_tokens(data: &ast::Fields<ParseField>, self_name: Path) -> (TokenStr
                     });
                 }
                 peek_impl.extend(quote! {
-                    if <#t>::peek(#peek_ix, tokenizer) {
+                    if <#t>::peek(pos + #peek_ix, tokenizer) {
                         peeked += 1;
                     }
                 });
@@ -499,7 +502,7 @@ impl ToTokens for ParseReceiver {
                             Path::from_string(&format!("Self::{v}")).unwrap()
                         );
                         parse_impl.extend(quote! {
-                            if { #peek } {
+                            if { let pos = 0usize; #peek } {
                                 return { #parse };
                             }
                         });
@@ -654,3 +657,62 @@ pub fn derive_resolve(input: TokenStream) -> TokenStream {
         }
     }.to_token_stream().into()
 }
+
+#[derive(FromDeriveInput)]
+#[darling(supports(enum_newtype))]
+struct EvalReceiver {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    data: ast::Data<ResolveVariant, ()>,
+}
+
+impl ToTokens for EvalReceiver {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let eval;
+
+        match &self.data {
+            ast::Data::Struct(_) => {
+                unimplemented!("structs not yet supported")
+            }
+            ast::Data::Enum(data) => {
+                let mut eval_matches = quote! {};
+                for v in data {
+                    let ident = &v.ident;
+                    eval_matches.extend(quote_spanned! {
+                        v.ident.span() =>
+                        Self::#ident(value) => crate::eval::EvalRef::eval_ref(value, pool, eval),
+                    });
+                }
+                eval = quote! {
+                    match self {
+                        #eval_matches
+                    }
+                };
+            }
+        }
+
+        let name = &self.ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        tokens.extend(quote! {
+            impl #impl_generics crate::eval::EvalNode for #name #ty_generics #where_clause {
+                fn eval_node(
+                    &self,
+                    pool: &crate::parser::parse::NodePool,
+                    eval: &mut crate::eval::Evaluator
+                ) -> crate::eval::EvalResult {
+                    #eval
+                }
+            }
+        });
+    }
+}
+
+#[proc_macro_derive(EvalNode)]
+pub fn derive_eval(input: TokenStream) -> TokenStream {
+    match EvalReceiver::from_derive_input(&syn::parse(input).expect("Couldn't parse item")) {
+        Ok(v) => v,
+        Err(e) => {
+            return e.write_errors().into();
+        }
+    }.to_token_stream().into()
+}
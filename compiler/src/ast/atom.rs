@@ -1,35 +1,92 @@
 
+use std::{sync::Arc, collections::HashMap};
+
 use dash_macros::{ParseNode, ResolveNode};
-use super::{expr::{Expr, IdentPath, ExprList}, token::{lit, kw}};
+use super::{expr::{Expr, IdentPath, ExprList}, decl::LetDecl, ty::{TypeExpr, TypeArgs}, token::{lit, kw, punct, Ident}, codegen::{Codegen, Splice}};
 use crate::{
     ast::token::delim,
-    checker::{resolve::ResolveNode, coherency::Checker, ty::Ty, path}, parser::parse::{NodePool, Node}, shared::logger::{Message, Level, LoggerRef}
+    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID, ScopeKind}, ty::{Ty, Variant}, entity::{Entity, Capture}, path},
+    parser::{
+        parse::{NodePool, Node, NodeID, ParseNode, ParseRef, RefToNode, FatalParseError, SeparatedWithTrailing},
+        tokenizer::{TokenIterator, TokenKind, InterpolatedPart}
+    },
+    shared::{src::{Src, ArcSpan}, logger::{Message, Level, Note, LoggerRef}},
+    eval::{EvalNode, EvalRef, EvalResult, Evaluator, Interrupt, bind_args, value::{Value, Function}}
 };
 
 #[derive(Debug, ParseNode)]
 #[parse(expected = "identifier")]
 pub enum ItemUseNode {
     This(kw::This),
-    Ident(IdentPath),
+    /// A generic item may be given explicit type arguments like 
+    /// `id::<int>`. The entity is recorded once resolved, so that its value 
+    /// can be found when evaluating
+    Ident(IdentPath, #[parse(skip)] Option<TypeArgs>, #[parse(skip)] Option<Entity>),
 }
 
-impl ResolveNode for ItemUseNode {
-    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+impl ItemUseNode {
+    pub(crate) fn name(&self, pool: &NodePool) -> path::IdentPath {
+        match self {
+            Self::Ident(i, _, _) => i.get(pool).to_path(pool),
+            Self::This(_) => path::IdentPath::new([path::Ident::from("this")], false)
+        }
+    }
+    /// Find the item `name` refers to when used at `span`
+    pub(crate) fn find_entity(name: &path::IdentPath, span: &ArcSpan, checker: &mut Checker) -> Option<Entity> {
+        let mut found = None;
         for scope in checker.scopes() {
-            if let Some(ent) = scope.entities().find(
-                &match self {
-                    Self::Ident(i) => i.get(pool).to_path(pool),
-                    Self::This(_) => path::IdentPath::new([path::Ident::from("this")], false)
+            if let Some(ent) = scope.entities().find(name) {
+                // Variables can't be used before they have been declared
+                if ent.visible_at(span) {
+                    found = Some((scope.id(), ent.clone()));
+                    break;
                 }
-            ) {
-                return Some(ent.ty());
             }
         }
-        None
+        let (scope, ent) = found?;
+        checker.capture(scope, name, &ent);
+        Some(ent)
     }
-    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+}
+
+impl ResolveNode for ItemUseNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ent = Self::find_entity(&self.name(pool), &self.span_or_builtin(pool), checker)?;
+        if let Self::Ident(_, _, entity) = self {
+            *entity = Some(ent.clone());
+        }
         match self {
-            Self::Ident(i) => logger.lock().unwrap().log(Message::new(
+            Self::Ident(_, Some(args), _) => args.get(pool).instantiate(&ent.ty(), pool, checker),
+            // A variable declared without a type gets it from the first value 
+            // assigned to it, or from the first use where a value of a known 
+            // type is expected. Until then only assignments to it resolve
+            _ if ent.ty().is_undecided() && !checker.in_assignment() => match checker.decided_ty(&ent.ty()) {
+                Some(ty) => Some(ty),
+                None => {
+                    let expected = checker.expected_ty().filter(|e| !e.is_unreal() && !e.is_partial())?;
+                    checker.decide_ty(&ent.ty(), expected.clone(), self.span_or_builtin(pool));
+                    Some(expected)
+                }
+            },
+            _ => Some(ent.ty()),
+        }
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, checker: &Checker, logger: LoggerRef) {
+        match self {
+            Self::Ident(_, _, Some(ent)) if ent.ty().is_undecided() => {
+                checker.expect_ty_decided(ent.ty(), self.span(pool));
+            }
+            Self::Ident(i, _, _) if checker.is_recursive_use(
+                &i.get(pool).to_path(pool), &i.get(pool).span_or_builtin(pool)
+            ) => logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Recursive function {} needs an explicit return type", i.get(pool).to_path(pool)),
+                i.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                "Its return type can't be inferred from a body that calls it, so declare it like '-> int'",
+                true
+            ))),
+            Self::Ident(i, _, _) => logger.lock().unwrap().log(Message::new(
                 Level::Error,
                 format!("Unknown item {}", i.get(pool).to_path(pool)),
                 i.get(pool).span_or_builtin(pool).as_ref()
@@ -43,15 +100,854 @@ impl ResolveNode for ItemUseNode {
     }
 }
 
+impl EvalNode for ItemUseNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let name = self.name(pool).to_string();
+        if let Some(value) = eval.local(&name) {
+            return Ok(value);
+        }
+        let entity = match self {
+            Self::Ident(_, _, entity) => entity.as_ref(),
+            Self::This(_) => None,
+        };
+        eval.entity_value(&name, entity, &self.span_or_builtin(pool))
+    }
+}
+
 #[derive(Debug, ParseNode, ResolveNode)]
 #[parse(expected = "expression")]
 pub enum AtomNode {
+    Lambda(Lambda),
     ClosedExpr(delim::Parenthesized<Expr>),
     Block(delim::Braced<ExprList>),
+    Collection(CollectionLit),
+    Codegen(Codegen),
+    Splice(Splice),
     ItemUse(ItemUse),
     String(lit::String),
+    InterpolatedString(InterpolatedString),
     Float(lit::Float),
     Int(lit::Int),
     Bool(lit::Bool),
     Void(lit::Void),
+    None(lit::NoneLit),
+}
+
+impl EvalNode for AtomNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        match self {
+            // Lambdas need a reference to themselves to become values
+            Self::Lambda(lambda) => lambda.get(pool).to_value(*lambda, eval),
+            Self::ClosedExpr(expr) => expr.eval_ref(pool, eval),
+            Self::Block(block) => block.eval_ref(pool, eval),
+            Self::Collection(collection) => collection.eval_ref(pool, eval),
+            Self::Codegen(codegen) => codegen.eval_ref(pool, eval),
+            Self::Splice(splice) => splice.eval_ref(pool, eval),
+            Self::ItemUse(item) => item.eval_ref(pool, eval),
+            Self::String(string) => string.eval_ref(pool, eval),
+            Self::InterpolatedString(string) => string.eval_ref(pool, eval),
+            Self::Float(float) => float.eval_ref(pool, eval),
+            Self::Int(int) => int.eval_ref(pool, eval),
+            Self::Bool(bool) => bool.eval_ref(pool, eval),
+            Self::Void(void) => void.eval_ref(pool, eval),
+            Self::None(none) => none.eval_ref(pool, eval),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InterpolatedStringPart {
+    Str(String),
+    Hole(Expr),
+}
+
+/// A string literal like `$"Clicked {count} times"` whose `{...}` holes 
+/// contain expressions that are converted to strings
+#[derive(Debug)]
+pub struct InterpolatedStringNode {
+    parts: Vec<InterpolatedStringPart>,
+    span: ArcSpan,
+}
+pub type InterpolatedString = RefToNode<InterpolatedStringNode>;
+
+impl InterpolatedStringNode {
+    pub fn parts(&self) -> &[InterpolatedStringPart] {
+        &self.parts
+    }
+    fn holes(&self) -> Vec<Expr> {
+        self.parts.iter()
+            .filter_map(|p| match p {
+                InterpolatedStringPart::Hole(e) => Some(*e),
+                InterpolatedStringPart::Str(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl Node for InterpolatedStringNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        self.parts.iter()
+            .filter_map(|p| match p {
+                InterpolatedStringPart::Hole(e) => Some(e as &dyn ResolveRef),
+                InterpolatedStringPart::Str(_) => None,
+            })
+            .collect()
+    }
+    fn span(&self, _: &NodePool) -> Option<ArcSpan> {
+        Some(self.span.clone())
+    }
+}
+
+impl ParseNode for InterpolatedStringNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        if !Self::peek(0, tokenizer) {
+            tokenizer.expected("interpolated string");
+            return Err(FatalParseError);
+        }
+        let token = tokenizer.next().unwrap();
+        let TokenKind::InterpolatedString(parts) = token.kind else { unreachable!() };
+        let mut res = Vec::new();
+        for part in parts {
+            match part {
+                InterpolatedPart::Str(s) if s.is_empty() => {}
+                InterpolatedPart::Str(s) => res.push(InterpolatedStringPart::Str(s)),
+                InterpolatedPart::Hole(tree) => res.push(InterpolatedStringPart::Hole(
+                    Expr::parse_complete(pool, src.clone(), tree)?
+                )),
+            }
+        }
+        Ok(pool.add(Self { parts: res, span: ArcSpan(src, token.span.1) }))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        tokenizer.peek(pos).is_some_and(|t| matches!(t.kind, TokenKind::InterpolatedString(_)))
+    }
+}
+
+impl ResolveNode for InterpolatedStringNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let holes = self.holes();
+        let tys = checker.with_expected_ty(None, |checker| {
+            holes.iter()
+                .map(|h| h.try_resolve_ref(pool, checker))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+        })?;
+        for (hole, ty) in holes.iter().zip(tys) {
+            let span = hole.get(pool).span(pool);
+            if checker.expect_ty_decided(ty.clone(), span.clone()) && !checker.is_string_convertible(&ty) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot convert a value of type {ty} to a string"),
+                    span.unwrap_or_default().as_ref()
+                ).note(Note::new(
+                    format!("Add a method 'fun {ty}::toString(this) -> string' to make it convertible"),
+                    true
+                )));
+            }
+        }
+        Some(Ty::String)
+    }
+}
+
+impl EvalNode for InterpolatedStringNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let mut res = String::new();
+        for part in &self.parts {
+            match part {
+                InterpolatedStringPart::Str(s) => res.push_str(s),
+                InterpolatedStringPart::Hole(e) => res.push_str(&e.eval_ref(pool, eval)?.to_string()),
+            }
+        }
+        Ok(Value::String(res))
+    }
+}
+
+/// An item of a list literal, or an entry like `key: value` of a map 
+/// literal
+#[derive(Debug)]
+pub struct CollectionItemNode {
+    item: Expr,
+    value: Option<(punct::Colon, Expr)>,
+}
+pub type CollectionItem = RefToNode<CollectionItemNode>;
+
+impl Node for CollectionItemNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.item, &self.value]
+    }
+}
+
+impl ParseNode for CollectionItemNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        // In `name: value` the name is a key rather than the binding of a 
+        // construction
+        let is_key = Ident::peek(0, tokenizer) && punct::Colon::peek(1, tokenizer);
+        let prev = tokenizer.set_allow_constructs(tokenizer.allow_constructs() && !is_key);
+        let item = Expr::parse_ref(pool, src.clone(), tokenizer);
+        tokenizer.set_allow_constructs(prev);
+        let res = Self {
+            item: item?,
+            value: ParseRef::parse_ref(pool, src, tokenizer)?,
+        };
+        Ok(pool.add(res))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        Expr::peek(pos, tokenizer)
+    }
+}
+
+impl ResolveNode for CollectionItemNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "list or map items")]
+pub enum CollectionItemsNode {
+    /// `[:]` is an empty map
+    EmptyMap(punct::Colon),
+    Items(SeparatedWithTrailing<CollectionItem, punct::Comma>),
+}
+
+/// A list literal like `[1, 2, 3]` or a map literal like `["a": 1, "b": 2]`. 
+/// The types of the items are the ones the collection is expected to have, 
+/// or otherwise those of the first item
+#[derive(Debug, ParseNode)]
+pub struct CollectionLitNode {
+    items: delim::Bracketed<Option<CollectionItems>>,
+}
+
+impl CollectionLitNode {
+    fn items(&self, pool: &NodePool) -> Vec<(Expr, Option<Expr>)> {
+        let Some(items) = self.items.get(pool).value else {
+            return vec![];
+        };
+        match &*items.get(pool) {
+            CollectionItemsNode::EmptyMap(_) => vec![],
+            CollectionItemsNode::Items(items) => items.iter()
+                .map(|i| (i.get(pool).item, i.get(pool).value.map(|v| v.1)))
+                .collect(),
+        }
+    }
+    fn is_map(&self, pool: &NodePool) -> bool {
+        let empty_map = self.items.get(pool).value
+            .is_some_and(|i| matches!(*i.get(pool), CollectionItemsNode::EmptyMap(_)));
+        empty_map || self.items(pool).iter().any(|i| i.1.is_some())
+    }
+}
+
+/// Resolve the items of a collection literal and find their common type, 
+/// which is `expected` if given
+fn resolve_items(items: &[Expr], expected: Option<Ty>, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+    let tys = checker.with_expected_ty(expected.clone(), |checker| {
+        items.iter()
+            .map(|i| i.try_resolve_ref(pool, checker))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+    })?;
+    // An empty collection can hold items of any type, like `none`
+    let item_ty = expected
+        .filter(|t| !t.is_unreal())
+        .or_else(|| tys.iter().find(|t| !t.is_unreal()).cloned())
+        .unwrap_or(Ty::Never);
+    for (item, ty) in items.iter().zip(tys) {
+        checker.expect_ty_eq(item_ty.clone(), ty, item.get(pool).span(pool));
+    }
+    Some(item_ty)
+}
+
+impl ResolveNode for CollectionLitNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let items = self.items(pool);
+        let expected = checker.expected_ty();
+        if !self.is_map(pool) {
+            let expected = match expected.as_ref().map(Ty::reduce) {
+                Some(Ty::List { ty }) => Some(ty.as_ref().clone()),
+                _ => None,
+            };
+            let items = items.into_iter().map(|i| i.0).collect::<Vec<_>>();
+            let ty = resolve_items(&items, expected, pool, checker)?;
+            return Some(Ty::List { ty: ty.into() });
+        }
+        let (expected_key, expected_value) = match expected.as_ref().map(Ty::reduce) {
+            Some(Ty::Map { key, value }) => (Some(key.as_ref().clone()), Some(value.as_ref().clone())),
+            _ => (None, None),
+        };
+        let keys = items.iter().map(|i| i.0).collect::<Vec<_>>();
+        let values = items.iter().filter_map(|i| i.1).collect::<Vec<_>>();
+        // An expected key type has already been checked where it was written
+        let check_key = expected_key.is_none();
+        let key = resolve_items(&keys, expected_key, pool, checker)?;
+        let value = resolve_items(&values, expected_value, pool, checker)?;
+        for (item, _) in items.iter().filter(|i| i.1.is_none()) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "Expected a value for this key",
+                item.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new("Entries of map literals are written as 'key: value'", true)));
+        }
+        if check_key {
+            checker.expect_ty_hashable(&key, keys.first().and_then(|k| k.get(pool).span(pool)));
+        }
+        Some(Ty::Map { key: key.into(), value: value.into() })
+    }
+}
+
+impl EvalNode for CollectionLitNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let items = self.items(pool);
+        if !self.is_map(pool) {
+            return Ok(Value::List(
+                items.iter()
+                    .map(|i| i.0.eval_ref(pool, eval))
+                    .collect::<Result<_, _>>()?
+            ));
+        }
+        let mut entries: Vec<(Value, Value)> = Vec::new();
+        for (key, value) in items {
+            let key = key.eval_ref(pool, eval)?;
+            let value = match value {
+                Some(value) => value.eval_ref(pool, eval)?,
+                None => Value::None,
+            };
+            // Later entries replace earlier ones with the same key
+            match entries.iter_mut().find(|e| e.0 == key) {
+                Some(entry) => entry.1 = value,
+                None => entries.push((key, value)),
+            }
+        }
+        Ok(Value::Map(entries))
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct LambdaParamNode {
+    name: Ident,
+    ty: Option<(punct::Colon, TypeExpr)>,
+}
+
+impl ResolveNode for LambdaParamNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+/// An anonymous function like `(a, b: int) => a + b`. Parameters without a 
+/// type get theirs from the function type the lambda is expected to be
+#[derive(Debug, ParseNode)]
+pub struct LambdaNode {
+    params: delim::Parenthesized<Option<SeparatedWithTrailing<LambdaParam, punct::Comma>>>,
+    #[parse(peek_point)]
+    arrow: punct::FatArrow,
+    body: Expr,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+    #[parse(skip)]
+    captures: Vec<Capture>,
+}
+
+impl LambdaNode {
+    /// The locals of enclosing functions this lambda uses
+    pub fn captures(&self) -> &[Capture] {
+        &self.captures
+    }
+    /// Create a closure out of this lambda, capturing the current values of 
+    /// the locals it uses
+    fn to_value(&self, node: Lambda, eval: &Evaluator) -> EvalResult {
+        Ok(Value::Function(Function::Lambda {
+            node,
+            captures: self.captures.iter()
+                .filter_map(|c| Some((c.name.clone(), eval.local(&c.name)?)))
+                .collect(),
+        }))
+    }
+    pub(crate) fn call(
+        &self,
+        pool: &NodePool,
+        eval: &mut Evaluator,
+        captures: Vec<(String, Value)>,
+        args: Vec<(Option<String>, Value)>,
+        span: ArcSpan
+    ) -> EvalResult {
+        let params = self.params.get(pool).value.iter()
+            .flat_map(|p| p.iter())
+            .map(|p| p.get(pool).name.get(pool).to_string())
+            .collect::<Vec<_>>();
+        let args = bind_args(&params, args);
+        let locals = captures.into_iter()
+            .chain(params.into_iter().zip(args).map(|(p, a)| (p, a.unwrap_or(Value::Void))))
+            .collect();
+        eval.with_frame(locals, &span, |eval| self.body.eval_ref(pool, eval))
+    }
+}
+
+impl ResolveNode for LambdaNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let param_refs = self.params.get(pool).value.iter()
+            .flat_map(|p| p.iter().copied())
+            .collect::<Vec<_>>();
+        let expected_ty = checker.expected_ty();
+        let expected = match expected_ty.as_ref().map(Ty::reduce) {
+            Some(Ty::Function { params, ret_ty }) if params.len() == param_refs.len() => {
+                Some((params.clone(), *ret_ty.clone()))
+            }
+            _ => None,
+        };
+
+        let mut params = Vec::new();
+        let mut uninferred = Vec::new();
+        for (i, param) in param_refs.iter().enumerate() {
+            let param = param.get(pool);
+            let ty = match param.ty {
+                Some((_, ty)) => ty.try_resolve_ref(pool, checker)?,
+                None => match expected {
+                    Some((ref e, _)) => e[i].1.clone(),
+                    None => {
+                        uninferred.push(param.name);
+                        Ty::Invalid
+                    }
+                }
+            };
+            params.push((param.name.get(pool).to_string(), ty, param.span_or_builtin(pool)));
+        }
+
+        let body = {
+            let new_scope = self.scope.is_none();
+            let _scope = checker.enter_scope_of_kind(&mut self.scope, ScopeKind::Closure);
+            for (name, ty, span) in params.iter().filter(|_| new_scope) {
+                if let Err(old) = checker.scope().entities_mut().try_push(
+                    &path::IdentPath::new([path::Ident::from(name.as_str())], false),
+                    Entity::new(ty.clone(), span.clone(), true)
+                ) {
+                    let old_span = old.span();
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Parameter {name} defined multiple times"),
+                        span.as_ref()
+                    ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+                }
+            }
+            let ret_ty = expected.map(|e| e.1);
+            checker.with_expected_ty(ret_ty, |checker| self.body.try_resolve_ref(pool, checker))?
+        };
+        // Closures return what their `return`s and their body evaluate to, 
+        // which is checked against the expected function type by the caller
+        let mut returns = checker.returns_of(self.scope.unwrap());
+        returns.push((body, self.body.get(pool).span_or_builtin(pool)));
+        let body = checker.infer_return_ty(&returns);
+
+        // If the lambda was expected to be a function with a different number 
+        // of parameters, the type mismatch is reported instead
+        let expected_fn = matches!(expected_ty.as_ref().map(Ty::reduce), Some(Ty::Function { .. }));
+        for name in uninferred.into_iter().filter(|_| !expected_fn) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot infer the type of parameter '{}'", name.get(pool)),
+                name.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                format!("Give the parameter a type with '{}: Type'", name.get(pool)),
+                true
+            )));
+        }
+        self.captures = checker.captures_of(self.scope.unwrap());
+
+        Some(Ty::Function {
+            params: params.into_iter().map(|p| (Some(p.0), p.1)).collect(),
+            ret_ty: body.into(),
+        })
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct ConstructPropertyNode {
+    name: Ident,
+    #[parse(peek_point)]
+    colon: punct::Colon,
+    value: Expr,
+}
+
+impl ResolveNode for ConstructPropertyNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.value.try_resolve_ref(pool, checker)
+    }
+}
+
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "property, child or let declaration")]
+pub enum ConstructItemNode {
+    Property(ConstructProperty),
+    Let(LetDecl),
+    Child(Expr),
+}
+
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "comma or semicolon")]
+pub enum ConstructSeparatorNode {
+    Comma(punct::Comma),
+    Semicolon(punct::Semicolon),
+}
+
+/// Construction of a struct (or a struct-like enum variant) from a block of 
+/// property assignments, child nodes and local `let`s, such as 
+/// `root: CCNode { pos: CCPoint { x: 0.0, y: 0.0 }, CCLabelBMFont { ... } }`
+#[derive(Debug)]
+pub struct ConstructNode {
+    binding: Option<(Ident, punct::Colon)>,
+    ty: IdentPath,
+    type_args: Option<TypeArgs>,
+    body: delim::Braced<Vec<(ConstructItem, Option<ConstructSeparator>)>>,
+    target: Option<Ty>,
+    scope: Option<ScopeID>,
+}
+pub type Construct = RefToNode<ConstructNode>;
+
+impl ConstructNode {
+    pub(crate) fn parse_with(
+        binding: Option<(Ident, punct::Colon)>,
+        ty: IdentPath,
+        type_args: Option<TypeArgs>,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            binding,
+            ty,
+            type_args,
+            body: ParseRef::parse_ref(pool, src, tokenizer)?,
+            target: None,
+            scope: None,
+        };
+        Ok(pool.add(res))
+    }
+    fn items(&self, pool: &NodePool) -> Vec<ConstructItem> {
+        self.body.get(pool).value.iter().map(|i| i.0).collect()
+    }
+    /// The child nodes added in this construction, in order
+    pub(crate) fn child_nodes(&self, pool: &NodePool) -> Vec<Expr> {
+        self.items(pool).into_iter()
+            .filter_map(|i| match *i.get(pool) {
+                ConstructItemNode::Child(e) => Some(e),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Node for ConstructNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.binding, &self.ty, &self.body]
+    }
+}
+
+impl ResolveNode for ConstructNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let name = self.ty.get(pool).to_path(pool);
+        if self.target.is_none() {
+            self.target = checker.scopes().find_map(|s| s.types().find(&name).cloned());
+        }
+        // Struct-like enum variants are constructed through `Enum::Variant {}`
+        if self.target.is_none() {
+            if let Some((parent, _)) = name.split_last() {
+                self.target = checker.scopes()
+                    .find_map(|s| s.types().find(&parent).cloned())
+                    .filter(|t| matches!(t, Ty::Enum { .. }));
+            }
+        }
+        // The struct may have been forward declared without its fields
+        if checker.is_forward_declared(self.target.as_ref()?) {
+            return None;
+        }
+        let target = match (self.target.clone()?, self.type_args) {
+            (target, Some(args)) => args.get(pool).instantiate(&target, pool, checker)?,
+            // Generic structs are otherwise instantiated like the expected 
+            // type, or inferred from the values given to their properties
+            (Ty::Generic { params, ty }, None) => {
+                let expected = checker.expected_ty().map(|e| match e.reduce() {
+                    Ty::Option { ty } => ty.as_ref().clone(),
+                    other => other.clone(),
+                });
+                match expected {
+                    Some(e @ Ty::Struct { .. }) if e.span() == ty.span() &&
+                        !matches!(e, Ty::Struct { ref args, .. } if args.iter().any(Ty::is_unreal)) => e,
+                    _ => Ty::Generic { params, ty },
+                }
+            }
+            (target, None) => target,
+        };
+        let target = checker.complete_struct(&target);
+
+        // The binding is visible to everything inside the construction
+        let new_scope = self.scope.is_none();
+        let _scope = checker.enter_scope(&mut self.scope);
+        if let (true, Some((binding, _))) = (new_scope, self.binding) {
+            let _ = checker.scope().entities_mut().try_push(
+                &path::IdentPath::new([path::Ident::from(binding.get(pool).to_string())], false),
+                Entity::new(target.clone(), binding.get(pool).span_or_builtin(pool), false)
+            );
+        }
+        let items = self.items(pool);
+        // Property values are expected to be of the type of their field
+        let known_fields = match target.reduce() {
+            Ty::Struct { name: _, fields, decl_span: _, args: _ } => Some(fields.clone()),
+            Ty::Generic { params: _, ty } => match ty.as_ref() {
+                Ty::Struct { name: _, fields, decl_span: _, args: _ } => Some(fields.clone()),
+                _ => None,
+            },
+            Ty::Enum { name: _, variants, decl_span: _ } => name.split_last()
+                .and_then(|(_, v)| variants.iter().find(|var| var.name == v.to_string()))
+                .and_then(|v| v.fields.clone()),
+            _ => None,
+        };
+        let tys = items.iter()
+            .map(|i| {
+                let expected = match *i.get(pool) {
+                    ConstructItemNode::Property(prop) => {
+                        let name = prop.get(pool).name.get(pool).to_string();
+                        known_fields.iter().flatten().find(|f| f.name == name).map(|f| match target {
+                            // Type parameters that haven't been inferred yet 
+                            // aren't expected to be anything in particular
+                            Ty::Generic { ref params, ty: _ } => f.ty.substitute(
+                                &params.iter().map(|p| (p.clone(), Ty::Invalid)).collect::<Vec<_>>()
+                            ),
+                            _ => f.ty.clone(),
+                        })
+                    }
+                    _ => None,
+                };
+                checker.with_expected_ty(expected, |checker| i.try_resolve_ref(pool, checker))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Option<Vec<_>>>()?;
+
+        let target = match target {
+            Ty::Generic { ref params, ty: _ } => {
+                let mut inferred = Vec::new();
+                for (item, ty) in items.iter().zip(&tys) {
+                    if let ConstructItemNode::Property(prop) = *item.get(pool) {
+                        let name = prop.get(pool).name.get(pool).to_string();
+                        if let Some(field) = known_fields.iter().flatten().find(|f| f.name == name) {
+                            field.ty.infer(ty, params, &mut inferred);
+                        }
+                    }
+                }
+                match checker.instantiate_inferred(&target, &inferred, self.ty.get(pool).span(pool)) {
+                    Ty::Invalid => return Some(Ty::Invalid),
+                    instance => instance,
+                }
+            }
+            other => other,
+        };
+        // Instances of generic structs are kept for evaluation
+        self.target = Some(target.clone());
+
+        let (fields, decl_span) = match target {
+            Ty::Struct { name: _, ref fields, ref decl_span, args: _ } => (fields, decl_span),
+            Ty::Enum { name: _, ref variants, decl_span: _ } => {
+                let v = name.split_last().map(|(_, v)| v.to_string()).unwrap_or_default();
+                match variants.iter().find(|var| var.name == v) {
+                    Some(Variant { fields: Some(fields), decl_span, .. }) => (fields, decl_span),
+                    Some(_) => {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            format!("Variant {name} has no fields"),
+                            self.ty.get(pool).span_or_builtin(pool).as_ref()
+                        ).note(Note::hint(
+                            format!("Use {name} without braces"),
+                            self.body.get(pool).span_or_builtin(pool).as_ref()
+                        )));
+                        return Some(target);
+                    }
+                    None => {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            format!("Enum {target} has no variant {v}"),
+                            self.ty.get(pool).span_or_builtin(pool).as_ref()
+                        ));
+                        return Some(Ty::Invalid);
+                    }
+                }
+            }
+            _ => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Type {target} can not be constructed"),
+                    self.ty.get(pool).span_or_builtin(pool).as_ref()
+                ));
+                return Some(Ty::Invalid);
+            }
+        };
+
+        let mut passed: HashMap<String, ArcSpan> = HashMap::new();
+        for (item, ty) in items.iter().zip(tys) {
+            let ConstructItemNode::Property(prop) = *item.get(pool) else {
+                continue;
+            };
+            let prop = prop.get(pool);
+            let name = prop.name.get(pool).to_string();
+            let span = prop.span_or_builtin(pool);
+            if let Some(old) = passed.get(&name) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Property '{name}' has already been given a value"),
+                    span.as_ref()
+                ).note(Note::new_at("Previous value here", old.as_ref())));
+                continue;
+            }
+            match fields.iter().find(|f| f.name == name) {
+                Some(f) if f.accessors.is_some_and(|a| !a.set) => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Property '{name}' has no setter"),
+                        span.as_ref()
+                    ).note(Note::new_at("Property declared here", f.decl_span.as_ref())));
+                }
+                Some(f) => {
+                    checker.expect_ty_eq(f.ty.clone(), ty, prop.value.get(pool).span(pool));
+                }
+                None => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Unknown property '{name}' for {target}"),
+                        span.as_ref()
+                    ));
+                }
+            }
+            passed.insert(name, span);
+        }
+
+        for child in self.child_nodes(pool) {
+            let ty = child.resolved_ty(pool)?;
+            if !ty.is_unreal() && !matches!(ty.reduce(), Ty::Struct { .. }) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot add a value of type {ty} as a child of {target}"),
+                    child.get(pool).span_or_builtin(pool).as_ref()
+                ).note(Note::new(
+                    "Only constructed nodes can be added as children. \
+                    To assign a property, use 'name: value'",
+                    true
+                )));
+            }
+        }
+
+        let missing = fields.iter()
+            .filter(|f| !f.has_default && f.accessors.is_none() && !passed.contains_key(&f.name))
+            .map(|f| format!("'{}'", f.name))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Missing values for properties {}", missing.join(", ")),
+                self.span_or_builtin(pool).as_ref()
+            ).note(Note::new_at(
+                format!("{name} declared here"),
+                decl_span.as_ref()
+            )));
+        }
+
+        Some(target)
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        if self.target.is_none() {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Unknown type {}", self.ty.get(pool).to_path(pool)),
+                self.ty.get(pool).span_or_builtin(pool).as_ref()
+            ));
+        }
+    }
+}
+
+impl EvalNode for ConstructNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        if let Some(child) = self.child_nodes(pool).first() {
+            return Err(Interrupt::Error(
+                "Child nodes can not be constructed at compile time".into(),
+                child.get(pool).span_or_builtin(pool)
+            ));
+        }
+        let name = match self.target {
+            Some(Ty::Struct { ref name, fields: _, decl_span: _, args: _ }) => name.clone(),
+            _ => self.ty.get(pool).to_path(pool).to_string(),
+        };
+        eval.with_scope(|eval| {
+            let mut fields = Vec::new();
+            for item in self.items(pool) {
+                match *item.get(pool) {
+                    ConstructItemNode::Property(prop) => {
+                        let prop = prop.get(pool);
+                        let name = prop.name.get(pool).to_string();
+                        // Setters need an existing struct to run on
+                        if let Some(Ty::Struct { name: _, ref fields, decl_span: _, args: _ }) = self.target {
+                            if fields.iter().any(|f| f.name == name && f.accessors.is_some()) {
+                                return Err(Interrupt::Error(
+                                    format!("Property '{name}' has accessors, so it can not be set at compile time"),
+                                    prop.span_or_builtin(pool)
+                                ));
+                            }
+                        }
+                        fields.push((name, prop.value.eval_ref(pool, eval)?));
+                    }
+                    ConstructItemNode::Let(decl) => {
+                        decl.eval_ref(pool, eval)?;
+                    }
+                    ConstructItemNode::Child(_) => {}
+                }
+            }
+            Ok(Value::Struct { name, fields })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::check_errors;
+
+    const POINT: &str = "struct Point {\n    x: int;\n    y: int;\n}\n";
+
+    fn check_literal(literal: &str) -> Vec<String> {
+        check_errors(&format!("{POINT}\nfun test(a: int) -> int {{\n    let p = {literal};\n    a\n}}\n"))
+    }
+
+    #[test]
+    fn complete_struct_literal() {
+        assert_eq!(check_literal("Point { x: 1, y: 2 }"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn missing_field() {
+        assert_eq!(check_literal("Point { x: 1 }"), ["Missing values for properties 'y'"]);
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert_eq!(check_literal("Point { x: 1, y: 2, z: 3 }"), ["Unknown property 'z' for Point"]);
+    }
+
+    #[test]
+    fn duplicate_field() {
+        assert_eq!(check_literal("Point { x: 1, y: 2, x: 3 }"), ["Property 'x' has already been given a value"]);
+    }
+
+    #[test]
+    fn wrong_field_type() {
+        assert_eq!(check_literal("Point { x: 1, y: \"2\" }"), ["Cannot convert from type string to int"]);
+    }
+
+    #[test]
+    fn structs_referring_to_each_other() {
+        assert_eq!(
+            check_errors(
+                "struct Line {\n    start: Point;\n    next: Line;\n}\n\
+                struct Point {\n    x: int;\n    y: int;\n}\n\
+                fun test(line: Line) -> Line {\n    Line { start: Point { x: 1, y: 2 }, next: line }\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
 }
@@ -0,0 +1,308 @@
+
+use std::sync::Arc;
+
+use crate::{
+    parser::{
+        parse::{NodePool, NodeID, Node, ParseNode, ParseRef, RefToNode, FatalParseError},
+        tokenizer::{Tokenizer, TokenIterator}
+    },
+    checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty, entity::Entity, path},
+    shared::{src::{Src, ArcSpan}, logger::{Message, Level, Note, LoggerRef}},
+    eval::{EvalNode, EvalRef, EvalResult, Evaluator, reflect::{self, Reflect, Code}, value::Value},
+    try_resolve_list
+};
+use super::{token::{kw, punct, delim}, expr::{Expr, Module}, atom::ItemUseNode};
+
+/// Code generated by a macro. It is parsed like the contents of a module, so
+/// that the items it declares end up next to the declaration the macro was
+/// applied to
+pub type Generated = delim::Braced<Module>;
+
+/// A hole like `@(field.name)` in a codegen template, which is filled with
+/// the value of its expression when the macro runs
+#[derive(Debug)]
+pub struct SpliceNode {
+    at: punct::At,
+    expr: delim::Parenthesized<Expr>,
+    /// Whether this splice was parsed inside a codegen template
+    in_template: bool,
+    /// The value the hole was filled with, if this splice is part of
+    /// generated code
+    fill: Option<Value>,
+    /// Where the code this splice is part of was added
+    generated_at: Option<ArcSpan>,
+    /// The item a spliced name refers to
+    entity: Option<Entity>,
+    /// Whether this splice is the name of a member, like `value.@(name)`
+    member_name: bool,
+}
+pub type Splice = RefToNode<SpliceNode>;
+
+impl SpliceNode {
+    /// The name the value of this splice stands for. Strings are names, and
+    /// declarations are referred to by their name
+    pub(crate) fn fill_name(&self) -> Option<String> {
+        match self.fill.as_ref()? {
+            Value::String(name) => Some(name.clone()),
+            Value::Reflect(r) => r.name().map(String::from),
+            _ => None,
+        }
+    }
+    /// Check the expression of a hole in a codegen template
+    fn resolve_hole(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ty = self.expr.try_resolve_ref(pool, checker)?;
+        if !self.in_template {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "'@(...)' can only be used inside codegen blocks",
+                self.span_or_builtin(pool).as_ref()
+            ));
+            return Some(Ty::Invalid);
+        }
+        if !reflect::is_spliceable(&ty) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot splice a value of type {ty} into generated code"),
+                self.expr.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                "Only strings, numbers, bools and values from the Reflect module can be spliced",
+                true
+            )));
+        }
+        Some(ty)
+    }
+    /// Mark this splice as the name of a member, which is looked up on the 
+    /// type of the value the member is accessed on instead of in scope
+    pub(crate) fn set_member_name(&mut self) {
+        self.member_name = true;
+    }
+}
+
+impl Node for SpliceNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.at, &self.expr]
+    }
+}
+
+impl ParseNode for SpliceNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        let in_template = pool.in_template();
+        let at: punct::At = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let (fill, generated_at) = pool.splice_fill(at.get(pool).span_or_builtin(pool).1.start).unzip();
+        // The spliced expression is run by the macro, so it is checked like
+        // any other code. Generated code only needs the value it produced
+        let expr = pool.with_template(fill.is_some(), |pool| ParseRef::parse_ref(pool, src, tokenizer))?;
+        Ok(pool.add(Self { at, expr, in_template, fill, generated_at, entity: None, member_name: false }))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        punct::At::peek(pos, tokenizer) && delim::Parenthesized::<delim::P>::peek(pos + 1, tokenizer)
+    }
+}
+
+impl ResolveNode for SpliceNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let Some(fill) = self.fill.clone() else {
+            return self.resolve_hole(pool, checker);
+        };
+        if self.member_name {
+            if self.fill_name().is_none() {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot use a value of type {} as the name of a member", fill.kind()),
+                    self.span_or_builtin(pool).as_ref()
+                ));
+            }
+            return Some(Ty::Void);
+        }
+        Some(match fill {
+            Value::Bool(_) => Ty::Bool,
+            Value::Int(_) => Ty::Int,
+            Value::Float(_) => Ty::Float,
+            Value::Reflect(ref r) if matches!(**r, Reflect::Expr(_)) => {
+                let Reflect::Expr(expr) = **r else { unreachable!() };
+                expr.try_resolve_ref(pool, checker)?
+            }
+            // Only names are left, since the holes of templates have been
+            // checked to be spliceable. They may refer to items visible 
+            // either in the template or where the code was added
+            _ => {
+                let name = path::IdentPath::parse(&self.fill_name()?);
+                let ent = ItemUseNode::find_entity(&name, &self.span_or_builtin(pool), checker)
+                    .or_else(|| ItemUseNode::find_entity(&name, self.generated_at.as_ref()?, checker))?;
+                self.entity = Some(ent.clone());
+                ent.ty()
+            }
+        })
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        if let Some(name) = self.fill_name().filter(|_| !self.member_name) {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Unknown item {name}"),
+                self.span_or_builtin(pool).as_ref()
+            ));
+        }
+    }
+}
+
+impl EvalNode for SpliceNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let Some(ref fill) = self.fill else {
+            return self.expr.eval_ref(pool, eval);
+        };
+        if let Value::Reflect(r) = fill {
+            if let Reflect::Expr(expr) = **r {
+                return expr.eval_ref(pool, eval);
+            }
+        }
+        match self.fill_name() {
+            Some(name) => match eval.local(&name) {
+                Some(value) => Ok(value),
+                None => eval.entity_value(&name, self.entity.as_ref(), &self.span_or_builtin(pool)),
+            },
+            None => Ok(fill.clone()),
+        }
+    }
+}
+
+/// A quasi-quoted block like `codegen { @(name) = 5; }`. Its body isn't
+/// checked or run, but instead becomes the template for the code the block
+/// evaluates to
+#[derive(Debug)]
+pub struct CodegenNode {
+    codegen_kw: kw::Codegen,
+    body: Generated,
+    /// The splices in the template, in the order they appear
+    splices: Vec<Splice>,
+}
+pub type Codegen = RefToNode<CodegenNode>;
+
+impl Node for CodegenNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.codegen_kw, &self.body]
+    }
+}
+
+impl ParseNode for CodegenNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        let codegen_kw = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let first = pool.next_id();
+        let body = pool.with_template(true, |pool| ParseRef::parse_ref(pool, src, tokenizer))?;
+        // Splices of codegen blocks nested in this one are filled in by
+        // those blocks
+        let nested = pool.all_of_since::<CodegenNode>(first).into_iter()
+            .flat_map(|c| c.get(pool).splices.clone())
+            .collect::<Vec<_>>();
+        let splices = pool.all_of_since::<SpliceNode>(first).into_iter()
+            .filter(|s| !nested.contains(s))
+            .collect();
+        Ok(pool.add(Self { codegen_kw, body, splices }))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        kw::Codegen::peek(pos, tokenizer)
+    }
+}
+
+impl ResolveNode for CodegenNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        try_resolve_list!(&self.splices, (pool, checker), s => s => s);
+        Some(reflect::reflect_ty("Code"))
+    }
+}
+
+impl EvalNode for CodegenNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let mut splices = Vec::new();
+        for splice in &self.splices {
+            let value = splice.eval_ref(pool, eval)?;
+            splices.push((splice.get(pool).span_or_builtin(pool), value));
+        }
+        Ok(Value::Reflect(Reflect::Code(Code {
+            template: self.body.get(pool).span_or_builtin(pool),
+            splices,
+            invocation: eval.invocation(),
+        }).into()))
+    }
+}
+
+/// Parse the template of generated code again as regular code, with its
+/// holes filled in. The code is added at `at`
+pub(crate) fn instantiate(code: &Code, at: ArcSpan, pool: &mut NodePool, logger: LoggerRef) -> Option<Generated> {
+    let src = code.template.0.clone();
+    let mut tokenizer = TokenIterator::from(Tokenizer::new_at(&src, code.template.1.start, logger));
+    pool.with_instantiated(code.clone(), at, |pool| Generated::parse_ref(pool, src.clone(), &mut tokenizer)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::{check_errors, check_errors_with_notes};
+
+    #[test]
+    fn generated_code_is_checked() {
+        assert_eq!(
+            check_errors(
+                "macro @withDouble(this: Reflect::Variable) -> Reflect::Code => codegen {\n    \
+                fun double() -> int => @(this.value()) * 2;\n    fun get() -> int => @(this);\n}\n\
+                @withDouble\nlet a = 21;\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn errors_in_generated_code() {
+        assert_eq!(
+            check_errors_with_notes(
+                "macro @withName(this: Reflect::Variable) -> Reflect::Code => codegen {\n    \
+                fun name() -> string => @(this.value());\n}\n@withName\nlet a = 21;\n"
+            ),
+            [["Cannot convert from type int to string", "In code generated by macro @withName here"]]
+        );
+        assert_eq!(
+            check_errors(
+                "macro @getter(this: Reflect::Variable) -> Reflect::Code => codegen {\n    \
+                fun get() -> int => @(\"missing\");\n}\n@getter\nlet a = 21;\n"
+            ),
+            ["Unknown item missing"]
+        );
+    }
+
+    const POINT: &str = "struct Point {\n    x: int;\n}\n";
+
+    #[test]
+    fn spliced_member_names() {
+        assert_eq!(
+            check_errors(&format!(
+                "{POINT}macro @getter(this: Reflect::Variable) -> Reflect::Code => codegen {{\n    \
+                fun get(p: Point) -> int => p.@(this.name);\n}}\n@getter\nlet x = 1;\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!(
+                "{POINT}macro @getter(this: Reflect::Variable) -> Reflect::Code => codegen {{\n    \
+                fun get(p: Point) -> int => p.@(this.value());\n}}\n@getter\nlet x = 1;\n"
+            )),
+            ["Cannot use a value of type Reflect::Expr as the name of a member"]
+        );
+    }
+
+    #[test]
+    fn splice_outside_codegen() {
+        assert_eq!(
+            check_errors("fun f() -> int => @(1);\n"),
+            ["'@(...)' can only be used inside codegen blocks"]
+        );
+    }
+
+    #[test]
+    fn splice_of_wrong_type() {
+        assert_eq!(
+            check_errors(
+                "fun f() -> int => 1;\n\
+                macro @m(this: Reflect::Variable) -> Reflect::Code => codegen {\n    let b = @(f);\n}\n"
+            ),
+            ["Cannot splice a value of type fun() -> int into generated code"]
+        );
+    }
+}
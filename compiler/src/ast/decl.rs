@@ -1,12 +1,24 @@
 
+use std::sync::Arc;
+
 use crate::{
-    parser::parse::{SeparatedWithTrailing, DontExpect, Node, NodePool},
+    parser::{
+        parse::{SeparatedWithTrailing, DontExpect, Node, NodePool, NodeID, ParseNode, ParseRef, FatalParseError, RefToNode},
+        tokenizer::TokenIterator
+    },
     add_compile_message,
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::Ty, entity::Entity, path},
-    shared::{src::ArcSpan, logger::{Message, Level, Note}}, try_resolve_ref
+    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID, ScopeKind, Context}, ty::{Ty, Field, Variant, Accessors}, entity::Entity, path, operators},
+    shared::{src::{ArcSpan, Src}, logger::{Message, Level, Note, LoggerRef}}, try_resolve_ref, try_resolve_list, ice,
+    eval::{EvalNode, EvalRef, EvalResult, Evaluator, Interrupt, reflect::{self, Reflect, Code}, value::Value}
 };
-use super::{token::{kw, op, punct, delim, Ident}, ty::TypeExpr, expr::{Expr, IdentPath, ExprList}};
-use dash_macros::{ParseNode, ResolveNode};
+use super::{
+    token::{kw, op, punct::{self, TerminatingSemicolon}, delim, Ident},
+    ty::{TypeExpr, TypeParams},
+    expr::{Expr, IdentPath, ExprList},
+    ops::{ArgList, resolve_args, check_args, eval_args},
+    codegen::Generated
+};
+use dash_macros::{ParseNode, ResolveNode, EvalNode};
 
 #[derive(Debug, ParseNode)]
 pub struct LetDeclNode {
@@ -18,10 +30,31 @@ pub struct LetDeclNode {
 
 impl ResolveNode for LetDeclNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let is_extern = checker.take_extern();
         let ty = try_resolve_ref!(self.ty, (pool, checker), Some((_, ty)) => ty);
-        let value = try_resolve_ref!(self.value, (pool, checker), Some((_, ty)) => ty);
-        let vty = checker.expect_ty_eq(value, ty, self.span(pool));
+        let value = checker.with_expected_ty(self.ty.is_some().then(|| ty.clone()), |checker| {
+            Some(try_resolve_ref!(self.value, (pool, checker), Some((_, ty)) => ty))
+        })?;
+        let vty = checker.expect_ty_eq(ty, value, self.span(pool));
         let name = self.name.get(pool).to_path(pool);
+        if is_extern {
+            // The value of an extern variable comes from the host, so only 
+            // its type is known
+            if let Some((_, value)) = self.value {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Extern variable {name} can not have a value"),
+                    value.get(pool).span_or_builtin(pool).as_ref()
+                ));
+            }
+            if self.ty.is_none() {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Extern variable {name} must have a type"),
+                    self.span_or_builtin(pool).as_ref()
+                ));
+            }
+        }
         match checker.scope().entities_mut().try_push(
             &name,
             Entity::new(
@@ -32,8 +65,8 @@ impl ResolveNode for LetDeclNode {
                     Ty::Undecided(name.to_string(), self.span_or_builtin(pool))
                 },
                 self.span_or_builtin(pool),
-                true
-            )
+                !is_extern
+            ).with_external(is_extern)
         ) {
             Ok(_) => {}
             Err(old) => {
@@ -49,13 +82,27 @@ impl ResolveNode for LetDeclNode {
     }
 }
 
+impl EvalNode for LetDeclNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let value = match self.value {
+            Some((_, value)) => value.eval_ref(pool, eval)?,
+            None => Value::Void,
+        };
+        eval.declare(self.name.get(pool).to_path(pool).to_string(), value);
+        Ok(Value::Void)
+    }
+}
+
 // mfw no &'static str in const generics 😢
 add_compile_message!(ThisParamMayNotHaveValue: "the 'this' parameter may not have a default value");
 
+/// A parameter of a function. Only parameters declared `var`, like 
+/// `var prop: Reflect::Field`, can be assigned a new value in the body
 #[derive(Debug, ParseNode)]
 #[parse(expected = "parameter")]
 pub enum FunParamNode {
     NamedParam {
+        var_kw: Option<kw::Var>,
         name: Ident,
         ty: (punct::Colon, TypeExpr),
         default_value: Option<(op::Seq, Expr)>,
@@ -73,39 +120,472 @@ impl ResolveNode for FunParamNode {
     }
 }
 
+#[derive(Debug, ParseNode)]
+#[parse(expected = "function body")]
+pub enum FunBodyNode {
+    Block(delim::Braced<ExprList>),
+    Expr(punct::FatArrow, Expr),
+}
+
+impl ResolveNode for FunBodyNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        match self {
+            Self::Block(block) => block.try_resolve_ref(pool, checker),
+            Self::Expr(_, expr) => expr.try_resolve_ref(pool, checker),
+        }
+    }
+}
+
+impl EvalNode for FunBodyNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        match self {
+            Self::Block(block) => block.eval_ref(pool, eval),
+            Self::Expr(_, expr) => expr.eval_ref(pool, eval),
+        }
+    }
+}
+
+/// Whether a function is a regular function or a macro that is run at 
+/// compile time when applied as a decorator. Binary operators declared 
+/// `commutative fun` also implement the operation with their operands swapped
+#[derive(Debug, ParseNode)]
+#[parse(expected = "function")]
+pub enum FunKindNode {
+    Fun(kw::Fun),
+    CommutativeFun(kw::Commutative, #[parse(peek_point)] kw::Fun),
+    Macro(kw::Macro),
+}
+
+impl ResolveNode for FunKindNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+/// The name of a function, which is either a path, an operator for overloads
+/// like `fun /(point: Point, factor: int) -> Point` or `[]` for indexing
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "function name")]
+pub enum FunNameNode {
+    BinOp(op::Binary),
+    UnOp(op::Unary),
+    Index(delim::Bracketed<delim::P>),
+    Path(IdentPath),
+}
+
+impl FunNameNode {
+    /// The path of this name, or `None` for operators, which are named after 
+    /// the types of their parameters
+    fn to_path(&self, pool: &NodePool) -> Option<path::IdentPath> {
+        match self {
+            Self::BinOp(_) | Self::UnOp(_) | Self::Index(_) => None,
+            Self::Path(path) => Some(path.get(pool).to_path(pool)),
+        }
+    }
+    /// The name of this function as written, like `Type::name` or `+`
+    fn to_name(&self, pool: &NodePool) -> String {
+        match self {
+            Self::BinOp(op) => op.get(pool).op().to_string(),
+            Self::UnOp(op) => op.get(pool).op().to_string(),
+            Self::Index(_) => String::from("[]"),
+            Self::Path(path) => path.get(pool).to_path(pool).to_string(),
+        }
+    }
+}
+
+/// What separates the parameters of a function from its return type. 
+/// Conversions use `as`, like `fun (point: Point) as Size { ... }`
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "'->' or 'as'")]
+pub enum RetTyMarkerNode {
+    Arrow(punct::Arrow),
+    As(kw::As),
+}
+
 #[derive(Debug, ParseNode)]
 pub struct FunDeclNode {
-    fun_kw: kw::Fun,
-    name: Option<IdentPath>,
-    params: delim::Parenthesized<SeparatedWithTrailing<FunParam, punct::Comma>>,
-    ret_ty: Option<(punct::Arrow, TypeExpr)>,
-    body: delim::Braced<ExprList>,
+    kind: FunKind,
+    name: Option<FunName>,
+    type_params: Option<TypeParams>,
+    params: delim::Parenthesized<Option<SeparatedWithTrailing<FunParam, punct::Comma>>>,
+    ret_ty: Option<(RetTyMarker, TypeExpr)>,
+    body: Option<FunBody>,
     #[parse(skip)]
     scope: Option<ScopeID>,
+    /// The scope the type parameters of a generic function are declared in
+    #[parse(skip)]
+    generics_scope: Option<ScopeID>,
+    /// The type `this` is inferred to be from the name of the function, if 
+    /// it couldn't be found
+    #[parse(skip)]
+    unresolved_this: Option<path::IdentPath>,
+    /// Whether the function has been declared on a previous pass
+    #[parse(skip)]
+    declared: bool,
+}
+
+impl FunDeclNode {
+    fn resolve_this_ty(&mut self, generics: &[Ty], pool: &NodePool, checker: &mut Checker, span: &ArcSpan) -> Option<Ty> {
+        // `fun Type::method(this)` is a method on `Type`
+        let parent = self.name.as_ref()
+            .and_then(|n| n.get(pool).to_path(pool))
+            .and_then(|n| n.split_last().map(|p| p.0));
+        let parent = parent.filter(|p| p.split_last().is_some());
+        let (parent, ty) = match (parent, checker.struct_body()) {
+            (Some(parent), _) => {
+                let ty = checker.scopes().find_map(|s| s.types().find(&parent).cloned());
+                self.unresolved_this = ty.is_none().then_some(parent.clone());
+                (parent, ty?)
+            }
+            // `fun method(this)` in the body of a struct is a method on the 
+            // struct
+            (None, Some(ty)) => (path::IdentPath::parse(&ty.method_namespace()), ty.clone()),
+            (None, None) => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Cannot infer the type of 'this'",
+                    span.as_ref()
+                ).note(Note::new(
+                    "Either give the parameter a type or declare the function as Type::name", true
+                )));
+                return Some(Ty::Invalid);
+            }
+        };
+        // Methods of generic structs take the type parameters of the struct 
+        // as their first type parameters, like `fun Box::get<T>(this) -> T`
+        match ty {
+            Ty::Generic { ref params, ty: _ } if params.len() <= generics.len() => {
+                Some(ty.instantiate(&generics[..params.len()]))
+            }
+            ref generic @ Ty::Generic { ref params, ty: _ } => {
+                let params = params.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot infer the type of 'this' for the generic type {generic}"),
+                    span.as_ref()
+                ).note(Note::new(
+                    format!("Declare the function with the type parameters of the struct, like 'fun {parent}::name<{params}>(this)'"),
+                    true
+                )));
+                Some(Ty::Invalid)
+            }
+            other => Some(other),
+        }
+    }
+    /// Whether this is a conversion declared like `fun (value: A) as B`
+    fn is_conversion(&self, pool: &NodePool) -> bool {
+        self.ret_ty.is_some_and(|(marker, _)| matches!(*marker.get(pool), RetTyMarkerNode::As(_)))
+    }
+    /// The name a conversion is registered under, logging an error if it 
+    /// isn't declared like `fun (value: A) as B`
+    fn conversion_name(&self, fty: &Ty, pool: &NodePool, checker: &mut Checker) -> Option<path::IdentPath> {
+        let Ty::Function { params, ret_ty } = fty else {
+            return None;
+        };
+        let error = if let Some(name) = self.name {
+            Some(("Conversions can not have a name", name.get(pool).span_or_builtin(pool)))
+        }
+        else if params.len() != 1 {
+            Some(("Conversions must take exactly one parameter", self.params.get(pool).span_or_builtin(pool)))
+        }
+        else {
+            None
+        };
+        if let Some((msg, span)) = error {
+            checker.logger().lock().unwrap().log(Message::new(Level::Error, msg, span.as_ref())
+                .note(Note::new("Declare conversions as 'fun (value: From) as To'", true)));
+            return None;
+        }
+        if params[0].1.is_unreal() || ret_ty.is_unreal() {
+            return None;
+        }
+        Some(path::IdentPath::new([
+            path::Ident::Conversion(params[0].1.clone(), ret_ty.as_ref().clone())
+        ], false))
+    }
+    /// The name of an operator overload like `fun +(a: A, b: B) -> C`, 
+    /// logging an error if it doesn't take the right amount of parameters
+    fn operator_name(&self, fty: &Ty, pool: &NodePool, checker: &mut Checker) -> Option<path::IdentPath> {
+        let Ty::Function { params, ret_ty: _ } = fty else {
+            return None;
+        };
+        let name = self.name?;
+        let op = match *name.get(pool) {
+            FunNameNode::BinOp(op) => Ok(op.get(pool).op()),
+            FunNameNode::UnOp(op) => Err(op.get(pool).op()),
+            FunNameNode::Index(_) => return self.index_name(params, pool, checker),
+            FunNameNode::Path(_) => return None,
+        };
+        let name = name.get(pool).to_name(pool);
+        let arity = match op {
+            Ok(op) if !op.is_overloadable() => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Operator '{name}' can not be overloaded"),
+                    self.span_or_builtin(pool).as_ref()
+                ));
+                return None;
+            }
+            // `+` and `-` are also prefix operators
+            Ok(op::BinaryOp::Add | op::BinaryOp::Sub) => "one or two parameters",
+            Ok(_) => "two parameters",
+            Err(_) => "one parameter",
+        };
+        let ident = match (op, params.as_slice()) {
+            (Ok(op), [a, b]) => path::Ident::BinOp(a.1.clone(), op, b.1.clone()),
+            (Ok(op::BinaryOp::Add), [a]) => path::Ident::UnOp(op::UnaryOp::Plus, a.1.clone()),
+            (Ok(op::BinaryOp::Sub), [a]) => path::Ident::UnOp(op::UnaryOp::Neg, a.1.clone()),
+            (Err(op), [a]) => path::Ident::UnOp(op, a.1.clone()),
+            _ => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Operator '{name}' must take {arity}"),
+                    self.span_or_builtin(pool).as_ref()
+                ));
+                return None;
+            }
+        };
+        if params.iter().any(|p| p.1.is_unreal()) {
+            return None;
+        }
+        Some(path::IdentPath::new([ident], false))
+    }
+    /// The name of an indexing function like `fun [](list: List, index: int)`
+    fn index_name(&self, params: &[(Option<String>, Ty)], pool: &NodePool, checker: &mut Checker) -> Option<path::IdentPath> {
+        let [target, index] = params else {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "Operator '[]' must take two parameters",
+                self.span_or_builtin(pool).as_ref()
+            ));
+            return None;
+        };
+        if target.1.is_unreal() || index.1.is_unreal() {
+            return None;
+        }
+        Some(path::IdentPath::new([path::Ident::Index(target.1.clone(), index.1.clone())], false))
+    }
+    fn param_nodes(&self, pool: &NodePool) -> Vec<FunParam> {
+        self.params.get(pool).value.iter()
+            .flat_map(|p| p.iter().copied())
+            .collect()
+    }
+    /// The names of the parameters that have default values
+    fn optional_params(&self, pool: &NodePool) -> Vec<String> {
+        self.param_nodes(pool).into_iter()
+            .filter_map(|p| match *p.get(pool) {
+                FunParamNode::NamedParam { var_kw: _, name, ty: _, default_value: Some(_) } => {
+                    Some(name.get(pool).to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+    /// Run this function at compile time with arguments that have been 
+    /// checked to match its parameters
+    pub(crate) fn call(
+        &self,
+        pool: &NodePool,
+        eval: &mut Evaluator,
+        args: Vec<(Option<String>, Value)>,
+        span: ArcSpan
+    ) -> EvalResult {
+        let Some(body) = self.body else {
+            return Err(Interrupt::Error("Extern functions can not be called at compile time".into(), span));
+        };
+        let params = self.param_nodes(pool);
+        let names = params.iter()
+            .map(|p| match *p.get(pool) {
+                FunParamNode::NamedParam { name, .. } => name.get(pool).to_string(),
+                FunParamNode::ThisParam { .. } => String::from("this"),
+            })
+            .collect::<Vec<_>>();
+        let values = crate::eval::bind_args(&names, args);
+        let mut locals = Vec::new();
+        for ((param, name), value) in params.iter().zip(names).zip(values) {
+            let value = match (value, &*param.get(pool)) {
+                (Some(value), _) => value,
+                // Default values are evaluated in the scope of the caller
+                (None, FunParamNode::NamedParam { default_value: Some((_, value)), .. }) => value.eval_ref(pool, eval)?,
+                (None, _) => Value::Void,
+            };
+            locals.push((name, value));
+        }
+        eval.with_frame(locals, &span, |eval| body.eval_ref(pool, eval))
+    }
+    /// The type of a function with the parameters `params` and the return 
+    /// type `ret_ty`, and its full type with the type parameters `generics`
+    fn signature(params: Vec<(String, Ty, ArcSpan)>, ret_ty: Ty, generics: Vec<Ty>) -> (Ty, Ty) {
+        let fty = Ty::Function {
+            params: params.into_iter().map(|p| (Some(p.0), p.1)).collect(),
+            ret_ty: ret_ty.into(),
+        };
+        // Generic functions are instantiated where they are used
+        let full_ty = if generics.is_empty() {
+            fty.clone()
+        }
+        else {
+            Ty::Generic { params: generics, ty: fty.clone().into() }
+        };
+        (fty, full_ty)
+    }
+    /// Declare the function in the current scope with the type `full_ty`, 
+    /// naming it after its signature `fty` if it's an operator or conversion
+    fn declare(&mut self, fty: &Ty, full_ty: Ty, is_extern: bool, pool: &NodePool, checker: &mut Checker) {
+        self.declared = true;
+        // Methods declared with an explicitly typed `this` are placed in the 
+        // namespace of that type
+        let this_ty = match fty {
+            Ty::Function { params, ret_ty: _ } => params.first()
+                .filter(|p| p.0.as_deref() == Some("this"))
+                .map(|p| p.1.clone()),
+            _ => None,
+        };
+        // Decorators take the item they decorate as `this`, but aren't 
+        // methods on its type. Methods declared in the body of a struct are 
+        // already in its namespace
+        let in_body = |this: &Ty| checker.struct_body().is_some_and(|s| s.reduce().to_string() == this.reduce().to_string());
+        let is_conversion = self.is_conversion(pool);
+        let name = match self.name.map(|n| n.get(pool).to_path(pool)) {
+            _ if is_conversion => self.conversion_name(fty, pool, checker),
+            Some(Some(name)) => Some(match this_ty {
+                Some(ref this) if !this.is_unreal() && !in_body(this) && name.split_last().is_some_and(|p| {
+                    p.0.split_last().is_none() && !matches!(p.1, path::Ident::Decorator(_))
+                }) => {
+                    path::IdentPath::parse(&this.method_namespace()).join(name.split_last().unwrap().1.clone())
+                }
+                _ => name,
+            }),
+            Some(None) => self.operator_name(fty, pool, checker),
+            None => None,
+        };
+        let name = match (self.type_params, self.name.map(|n| n.get(pool).to_path(pool))) {
+            (Some(type_params), Some(None)) => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Conversions and operators can not be generic",
+                    type_params.get(pool).span_or_builtin(pool).as_ref()
+                ));
+                None
+            }
+            _ => name,
+        };
+        let binop = name.as_ref()
+            .and_then(|n| n.split_last())
+            .is_some_and(|(_, ident)| matches!(ident, path::Ident::BinOp(..)));
+        let commutative = matches!(*self.kind.get(pool), FunKindNode::CommutativeFun(..));
+        if commutative && !binop {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "Only binary operators can be commutative",
+                self.kind.get(pool).span_or_builtin(pool).as_ref()
+            ));
+        }
+        // Conversions and operators are looked up from every enclosing scope, 
+        // so they may not shadow existing ones
+        let shadowed = name.as_ref()
+            .filter(|_| is_conversion || self.name.is_some_and(|n| n.get(pool).to_path(pool).is_none()))
+            .and_then(|name| name.split_last())
+            .and_then(|(_, ident)| operators::find_conflict(checker, ident, commutative))
+            .map(|e| e.span());
+        if let (Some(ref name), Some(old_span)) = (&name, shadowed) {
+            let span = self.span_or_builtin(pool);
+            let msg = Message::new(
+                Level::Error,
+                format!("Name {} has already been defined", name),
+                span.as_ref()
+            );
+            checker.logger().lock().unwrap().log(if old_span == ArcSpan::builtin() {
+                msg.note(Note::new("Previous definition is built into the compiler", false))
+            }
+            else {
+                msg.note(Note::new_at("Previous definition here", old_span.as_ref()))
+            });
+        }
+        else if let Some(ref name) = name {
+            if let Err(old) = checker.scope().entities_mut().try_push(
+                name,
+                Entity::new(full_ty, self.span_or_builtin(pool), false)
+                    .with_external(is_extern)
+                    .with_macro(matches!(*self.kind.get(pool), FunKindNode::Macro(_)))
+                    .with_optional_params(self.optional_params(pool))
+                    .with_commutative(binop && commutative)
+            ) {
+                let old_span = old.span();
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Name {} has already been defined", name),
+                    self.span_or_builtin(pool).as_ref()
+                ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+            }
+        }
+    }
 }
 
 impl ResolveNode for FunDeclNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let is_extern = checker.take_extern();
+        // Type parameters are visible in the parameters, the return type and 
+        // the body, where they can only be used as opaque types
+        let generics = self.type_params.map(|t| t.get(pool).params(pool)).unwrap_or_default();
+        let new_generics = self.generics_scope.is_none();
+        let mut generics_scope = (!generics.is_empty()).then(|| checker.enter_scope(&mut self.generics_scope));
+        for param in generics.iter().filter(|_| new_generics) {
+            push_type_decl(&path::IdentPath::parse(&param.to_string()), param.clone(), param.span(), checker);
+        }
         let mut params = Vec::new();
-        for param in self.params.get(pool).value.iter() {
+        for param in self.param_nodes(pool) {
+            let span = param.get(pool).span_or_builtin(pool);
             match *param.get(pool) {
-                FunParamNode::NamedParam { name, ty, default_value } => {
-                    let span = param.get(pool).span(pool);
+                FunParamNode::NamedParam { var_kw: _, name, ty, default_value } => {
                     let ty = ty.1.try_resolve_ref(pool, checker)?;
-                    let v = try_resolve_ref!(default_value, (pool, checker), Some((_, ty)) => ty);
-                    checker.expect_ty_eq(ty.clone(), v, span.clone());
-                    params.push((name.get(pool).to_string(), ty, span.unwrap_or(ArcSpan::builtin())));
+                    let v = checker.with_expected_ty(Some(ty.clone()), |checker| {
+                        Some(try_resolve_ref!(default_value, (pool, checker), Some((_, ty)) => ty))
+                    })?;
+                    checker.expect_ty_eq(ty.clone(), v, Some(span.clone()));
+                    params.push((name.get(pool).to_string(), ty, span));
+                }
+                FunParamNode::ThisParam { this_kw: _, ty, _invalid_value: _ } => {
+                    let ty = match ty {
+                        Some((_, ty)) => ty.try_resolve_ref(pool, checker)?,
+                        None => self.resolve_this_ty(&generics, pool, checker, &span)?,
+                    };
+                    if !params.is_empty() {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            "'this' must be the first parameter",
+                            span.as_ref()
+                        ));
+                    }
+                    params.push((String::from("this"), ty, span));
                 }
-                FunParamNode::ThisParam { this_kw: _, ty, _invalid_value: _ } => todo!()
             }
         }
         let ret_ty = try_resolve_ref!(self.ret_ty, (pool, checker), Some((_, ty)) => ty);
+        // With a declared return type the signature is known before the 
+        // body, so the function is declared first to let it call itself
+        if !self.declared && (self.ret_ty.is_some() || is_extern) {
+            drop(generics_scope);
+            let (fty, full_ty) = Self::signature(params.clone(), ret_ty.clone(), generics.clone());
+            self.declare(&fty, full_ty, is_extern, pool, checker);
+            generics_scope = (!generics.is_empty()).then(|| checker.enter_scope(&mut self.generics_scope));
+        }
+        else if self.scope.is_none() && self.ret_ty.is_none() {
+            if let Some(Some(name)) = self.name.map(|n| n.get(pool).to_path(pool)) {
+                checker.add_inferred_fun(name, self.span_or_builtin(pool));
+            }
+        }
         let body = {
-            let _scope = checker.enter_scope(&mut self.scope);
-            for (name, ty, span) in &params {
+            // Parameters are only added once, even if the body takes multiple 
+            // passes to resolve
+            let new_scope = self.scope.is_none();
+            let _scope = checker.enter_scope_of_kind(&mut self.scope, ScopeKind::Function);
+            let param_nodes = self.param_nodes(pool);
+            for ((name, ty, span), node) in params.iter().zip(param_nodes).filter(|_| new_scope) {
+                let read_only = matches!(*node.get(pool), FunParamNode::NamedParam { var_kw: None, .. });
                 if let Err(old) = checker.scope().entities_mut().try_push(
                     &path::IdentPath::new([path::Ident::from(name.as_str())], false),
-                    Entity::new(ty.clone(), self.span_or_builtin(pool), true)
+                    Entity::new(ty.clone(), span.clone(), true).with_read_only(read_only)
                 ) {
                     let old_span = old.span();
                     checker.logger().lock().unwrap().log(Message::new(
@@ -115,35 +595,1457 @@ impl ResolveNode for FunDeclNode {
                     ).note(Note::new_at("Previous definition here", old_span.as_ref())));
                 }
             }
-            self.body.try_resolve_ref(pool, checker)?
+            let expected = self.ret_ty.is_some().then(|| ret_ty.clone());
+            checker.set_return_ty(expected.clone());
+            checker.with_expected_ty(expected, |checker| {
+                Some(try_resolve_ref!(self.body, (pool, checker), Some(body) => body))
+            })?
         };
-        checker.expect_ty_eq(ret_ty.clone(), body.clone(), self.body.get(pool).span(pool));
-
-        let fty = Ty::Function {
-            params: params.into_iter().map(|p| (Some(p.0), p.1)).collect(),
-            ret_ty: ret_ty.into(),
+        // Without a declared return type, the function returns what its 
+        // `return`s and its body evaluate to
+        let ret_ty = match self.body {
+            _ if self.ret_ty.is_some() || is_extern => ret_ty,
+            Some(b) => {
+                let mut returns = checker.returns_of(self.scope.unwrap());
+                returns.push((body.clone(), b.get(pool).span_or_builtin(pool)));
+                checker.infer_return_ty(&returns)
+            }
+            None => Ty::Invalid,
         };
-        if let Some(ref name) = self.name.as_ref().map(|n| n.get(pool).to_path(pool)) {
-            if let Err(old) = checker.scope().entities_mut().try_push(
-                name,
-                Entity::new(fty.clone(), self.span_or_builtin(pool), false)
-            ) {
-                let old_span = old.span();
+        match self.body {
+            Some(b) if is_extern => {
                 checker.logger().lock().unwrap().log(Message::new(
                     Level::Error,
-                    format!("Name {} has already been defined", name),
+                    "Extern functions can not have a body",
+                    b.get(pool).span_or_builtin(pool).as_ref()
+                ));
+            }
+            Some(b) if self.ret_ty.is_some() => {
+                checker.expect_ty_eq(ret_ty.clone(), body.clone(), b.get(pool).span(pool));
+            }
+            Some(_) => {}
+            None if !is_extern => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    "Function has no body",
                     self.span_or_builtin(pool).as_ref()
-                ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+                ).note(Note::new(
+                    "If the function is provided by the host, declare it as 'extern fun'", true
+                )));
             }
+            None => {}
+        }
+        // The function itself is declared outside of its type parameters
+        drop(generics_scope);
+        let (fty, full_ty) = Self::signature(params, ret_ty, generics);
+        if !self.declared {
+            self.declare(&fty, full_ty.clone(), is_extern, pool, checker);
+        }
+        Some(full_ty)
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        if let Some(ref name) = self.unresolved_this {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Unknown type {name}"),
+                self.name.map(|n| n.get(pool).span_or_builtin(pool)).unwrap_or_default().as_ref()
+            ));
+        }
+    }
+}
+
+impl EvalNode for FunDeclNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        // Functions are looked up by the evaluator when they are referred to
+        Ok(Value::Void)
+    }
+}
+
+/// Declare `name` as a local of an accessor, unless it's already been 
+/// declared on a previous pass
+fn push_accessor_local(name: &str, ty: Ty, span: ArcSpan, checker: &mut Checker) {
+    let _ = checker.scope().entities_mut().try_push(
+        &path::IdentPath::new([path::Ident::from(name)], false),
+        Entity::new(ty, span, true)
+    );
+}
+
+/// The getter of a computed property, like `get => this.pos.x`
+#[derive(Debug, ParseNode)]
+pub struct GetterNode {
+    get_kw: kw::Get,
+    body: FunBody,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for GetterNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let Some((this, ty)) = checker.property() else {
+            ice!("getter resolved outside of a property")
+        };
+        let _scope = checker.enter_scope_of_kind(&mut self.scope, ScopeKind::Function);
+        push_accessor_local("this", this, self.get_kw.get(pool).span_or_builtin(pool), checker);
+        checker.set_return_ty(Some(ty.clone()));
+        let body = checker.with_expected_ty(Some(ty.clone()), |checker| self.body.try_resolve_ref(pool, checker))?;
+        checker.expect_ty_eq(ty, body, self.body.get(pool).span(pool));
+        Some(Ty::Void)
+    }
+}
+
+impl GetterNode {
+    /// Run this getter at compile time on the struct `this`
+    pub(crate) fn call(&self, pool: &NodePool, eval: &mut Evaluator, this: Value, span: ArcSpan) -> EvalResult {
+        eval.with_frame(vec![(String::from("this"), this)], &span, |eval| self.body.eval_ref(pool, eval))
+    }
+}
+
+/// The setter of a computed property, like `set(value) => this.pos.x = value`. 
+/// The parameter has the type of the property
+#[derive(Debug, ParseNode)]
+pub struct SetterNode {
+    set_kw: kw::Set,
+    param: delim::Parenthesized<Ident>,
+    body: FunBody,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for SetterNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let Some((this, ty)) = checker.property() else {
+            ice!("setter resolved outside of a property")
+        };
+        let _scope = checker.enter_scope_of_kind(&mut self.scope, ScopeKind::Function);
+        push_accessor_local("this", this, self.set_kw.get(pool).span_or_builtin(pool), checker);
+        let param = self.param.get(pool).value;
+        push_accessor_local(&param.get(pool).to_string(), ty, param.get(pool).span_or_builtin(pool), checker);
+        checker.set_return_ty(Some(Ty::Void));
+        checker.with_expected_ty(None, |checker| self.body.try_resolve_ref(pool, checker))?;
+        Some(Ty::Void)
+    }
+}
+
+impl SetterNode {
+    /// Run this setter at compile time on the struct `this`
+    pub(crate) fn call(&self, pool: &NodePool, eval: &mut Evaluator, this: Value, value: Value, span: ArcSpan) -> EvalResult {
+        let param = self.param.get(pool).value.get(pool).to_string();
+        eval.with_frame(
+            vec![(String::from("this"), this), (param, value)],
+            &span,
+            |eval| self.body.eval_ref(pool, eval)
+        )
+    }
+}
+
+/// A field a computed property is derived from, like `depends x`
+#[derive(Debug, ParseNode)]
+pub struct DependsNode {
+    depends_kw: kw::Depends,
+    field: Ident,
+}
+
+impl ResolveNode for DependsNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let Some((this, _)) = checker.property() else {
+            ice!("dependency resolved outside of a property")
+        };
+        let name = self.field.get(pool).to_string();
+        if checker.find_field(&this, &name).is_none() {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Unknown field '{name}' on {this}"),
+                self.field.get(pool).span_or_builtin(pool).as_ref()
+            ));
         }
-        Some(fty)
+        Some(Ty::Void)
     }
 }
 
 #[derive(Debug, ParseNode, ResolveNode)]
-#[parse(expected = "item declaration")]
-pub enum DeclNode {
-    LetDecl(LetDecl),
-    FunDecl(FunDecl),
+#[parse(expected = "get, set or depends")]
+pub enum AccessorNode {
+    Get(Getter),
+    Set(Setter),
+    Depends(Depends),
+}
+
+/// A property bound to fields of the same name on the struct, like 
+/// `pos: Point <=> { x, y }`. Reading the property constructs a `Point` out 
+/// of `this.x` and `this.y`, and assigning to it assigns both fields
+#[derive(Debug)]
+pub struct BindingNode {
+    bind: punct::Bind,
+    fields: delim::Braced<Option<SeparatedWithTrailing<Ident, punct::Comma>>>,
+}
+pub type Binding = RefToNode<BindingNode>;
+
+impl Node for BindingNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.bind, &self.fields]
+    }
+}
+
+impl ParseNode for BindingNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        let bind = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let fields = ParseRef::parse_ref(pool, src, tokenizer)?;
+        // The list of fields is part of the declaration like a default value 
+        // would be, so the field still ends in a semicolon
+        tokenizer.forget_braced();
+        Ok(pool.add(Self { bind, fields }))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        punct::Bind::peek(pos, tokenizer)
+    }
+}
+
+impl BindingNode {
+    fn field_names(&self, pool: &NodePool) -> Vec<(String, ArcSpan)> {
+        self.fields.get(pool).value.iter()
+            .flat_map(|f| f.iter().map(|f| (f.get(pool).to_string(), f.get(pool).span_or_builtin(pool))))
+            .collect()
+    }
+    /// Read the bound fields of `this` at compile time
+    fn eval_get(&self, pool: &NodePool, ty: &Ty, this: Value, span: ArcSpan) -> EvalResult {
+        let Value::Struct { name: _, fields: values } = this else {
+            return Err(Interrupt::Error(format!("Cannot read bound fields of {}", this.kind()), span));
+        };
+        let mut fields = Vec::new();
+        for (name, _) in self.field_names(pool) {
+            match values.iter().find(|v| v.0 == name) {
+                Some((_, value)) => fields.push((name, value.clone())),
+                None => return Err(Interrupt::Error(
+                    format!("Field '{name}' has no value at compile time"),
+                    span
+                )),
+            }
+        }
+        let name = match ty.reduce() {
+            Ty::Struct { name, fields: _, decl_span: _, args: _ } => name.clone(),
+            other => other.to_string(),
+        };
+        Ok(Value::Struct { name, fields })
+    }
+}
+
+impl ResolveNode for BindingNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let Some((this, ty)) = checker.property() else {
+            ice!("binding resolved outside of a property")
+        };
+        let Ty::Struct { name: _, fields: bound, decl_span: _, args: _ } = ty.reduce() else {
+            if !ty.is_unreal() {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot bind fields to a property of type {ty}"),
+                    self.bind.get(pool).span_or_builtin(pool).as_ref()
+                ).note(Note::new("Only properties with a struct type can be bound", false)));
+            }
+            return Some(Ty::Void);
+        };
+        let names = self.field_names(pool);
+        for (name, span) in &names {
+            let Some(target) = bound.iter().find(|f| &f.name == name) else {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Type {ty} has no field '{name}'"),
+                    span.as_ref()
+                ));
+                continue;
+            };
+            let Some(source) = checker.find_field(&this, name) else {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Unknown field '{name}' on {this}"),
+                    span.as_ref()
+                ).note(Note::new(format!("Bound fields must exist on both {this} and {ty}"), false)));
+                continue;
+            };
+            if !source.ty.convertible(&target.ty) || !target.ty.convertible(&source.ty) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!(
+                        "Cannot bind field '{name}' of type {} to field of type {}",
+                        source.ty, target.ty
+                    ),
+                    span.as_ref()
+                ).note(Note::new_at(
+                    format!("Field '{name}' of {ty} declared here"),
+                    target.decl_span.as_ref()
+                )));
+            }
+        }
+        // Reading the property constructs it, so every field without a 
+        // default has to be bound
+        let missing = bound.iter()
+            .filter(|f| !f.has_default && f.accessors.is_none() && !names.iter().any(|n| n.0 == f.name))
+            .map(|f| format!("'{}'", f.name))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Binding to {ty} is missing fields {}", missing.join(", ")),
+                self.fields.get(pool).span_or_builtin(pool).as_ref()
+            ));
+        }
+        Some(Ty::Void)
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct StructFieldNode {
+    name: Ident,
+    ty: (punct::Colon, TypeExpr),
+    default_value: Option<(op::Seq, Expr)>,
+    binding: Option<Binding>,
+    accessors: Option<delim::Braced<Vec<(Accessor, TerminatingSemicolon)>>>,
+}
+
+impl StructFieldNode {
+    fn accessor_nodes(&self, pool: &NodePool) -> Vec<Accessor> {
+        self.accessors.iter()
+            .flat_map(|a| a.get(pool).value.iter().map(|a| a.0).collect::<Vec<_>>())
+            .collect()
+    }
+    /// Which accessors this field has if it's a computed property. Bindings 
+    /// always have both
+    fn accessor_kinds(&self, pool: &NodePool) -> Option<Accessors> {
+        if self.binding.is_some() {
+            return Some(Accessors { get: true, set: true });
+        }
+        let nodes = self.accessor_nodes(pool);
+        self.accessors.is_some().then(|| Accessors {
+            get: nodes.iter().any(|a| matches!(*a.get(pool), AccessorNode::Get(_))),
+            set: nodes.iter().any(|a| matches!(*a.get(pool), AccessorNode::Set(_))),
+        })
+    }
+    /// The names of the fields this property depends on, either through 
+    /// `depends` or by being bound to them
+    fn dependencies(&self, pool: &NodePool) -> Vec<String> {
+        let mut res = self.binding
+            .map(|b| b.get(pool).field_names(pool).into_iter().map(|f| f.0).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for accessor in self.accessor_nodes(pool) {
+            if let AccessorNode::Depends(depends) = *accessor.get(pool) {
+                let name = depends.get(pool).field.get(pool).to_string();
+                if !res.contains(&name) {
+                    res.push(name);
+                }
+            }
+        }
+        res
+    }
+    fn getter(&self, pool: &NodePool) -> Option<Getter> {
+        self.accessor_nodes(pool).into_iter().find_map(|a| match *a.get(pool) {
+            AccessorNode::Get(g) => Some(g),
+            _ => None,
+        })
+    }
+    fn setter(&self, pool: &NodePool) -> Option<Setter> {
+        self.accessor_nodes(pool).into_iter().find_map(|a| match *a.get(pool) {
+            AccessorNode::Set(s) => Some(s),
+            _ => None,
+        })
+    }
+    /// Read this computed property of `this` at compile time
+    pub(crate) fn eval_get(&self, pool: &NodePool, eval: &mut Evaluator, this: Value, span: ArcSpan) -> EvalResult {
+        if let Some(binding) = self.binding {
+            let ty = self.ty.1.resolved_ty(pool).unwrap_or(Ty::Invalid);
+            return binding.get(pool).eval_get(pool, &ty, this, span);
+        }
+        match self.getter(pool) {
+            Some(getter) => getter.get(pool).call(pool, eval, this, span),
+            None => Err(Interrupt::Error(
+                format!("Property '{}' has no getter", self.name.get(pool)),
+                span
+            )),
+        }
+    }
+    /// Assign this computed property of `this` at compile time
+    pub(crate) fn eval_set(&self, pool: &NodePool, eval: &mut Evaluator, this: Value, value: Value, span: ArcSpan) -> EvalResult {
+        match self.setter(pool) {
+            Some(setter) => setter.get(pool).call(pool, eval, this, value, span),
+            // Bindings assign fields, which can't be done at compile time
+            None => Err(Interrupt::Error(
+                "Fields can not be assigned to at compile time".into(),
+                span
+            )),
+        }
+    }
 }
 
+impl ResolveNode for StructFieldNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let ty = self.ty.1.try_resolve_ref(pool, checker)?;
+        let v = checker.with_expected_ty(Some(ty.clone()), |checker| {
+            Some(try_resolve_ref!(self.default_value, (pool, checker), Some((_, v)) => v))
+        })?;
+        checker.expect_ty_eq(ty.clone(), v, self.span(pool));
+
+        // The accessors themselves are resolved by the struct, since they 
+        // need its type for `this`
+        let name = self.name.get(pool).to_string();
+        if let (Some(binding), Some(accessors)) = (self.binding, self.accessors) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Property {name} is bound to fields, so it can not have accessors"),
+                accessors.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new_at("Binding here", binding.get(pool).span_or_builtin(pool).as_ref())));
+        }
+        let mut seen: Vec<(bool, ArcSpan)> = Vec::new();
+        for accessor in self.accessor_nodes(pool) {
+            let is_get = match *accessor.get(pool) {
+                AccessorNode::Get(_) => true,
+                AccessorNode::Set(_) => false,
+                AccessorNode::Depends(depends) => {
+                    let field = depends.get(pool).field.get(pool);
+                    if field.to_string() == name {
+                        checker.logger().lock().unwrap().log(Message::new(
+                            Level::Error,
+                            format!("Property {name} can not depend on itself"),
+                            field.span_or_builtin(pool).as_ref()
+                        ));
+                    }
+                    continue;
+                }
+            };
+            let span = accessor.get(pool).span_or_builtin(pool);
+            if let Some((_, old)) = seen.iter().find(|s| s.0 == is_get) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Property {name} already has a {}", if is_get { "getter" } else { "setter" }),
+                    span.as_ref()
+                ).note(Note::new_at("Previous definition here", old.as_ref())));
+            }
+            seen.push((is_get, span));
+        }
+        if let Some(accessors) = self.accessor_kinds(pool) {
+            if !accessors.get && self.accessors.is_some() {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Property {name} must have a getter"),
+                    self.accessors.map(|a| a.get(pool).span_or_builtin(pool)).unwrap_or_default().as_ref()
+                ));
+            }
+            if let Some((_, value)) = self.default_value {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Property {name} has accessors, so it can not have a default value"),
+                    value.get(pool).span_or_builtin(pool).as_ref()
+                ));
+            }
+        }
+        Some(ty)
+    }
+}
+
+type StructFields = delim::Braced<Vec<(StructField, TerminatingSemicolon)>>;
+
+fn field_nodes(fields: &StructFields, pool: &NodePool) -> Vec<StructField> {
+    fields.get(pool).value.iter().map(|f| f.0).collect()
+}
+
+fn resolve_struct_fields(fields: &[StructField], pool: &NodePool, checker: &mut Checker) -> Option<Vec<Field>> {
+    let mut res: Vec<Field> = Vec::new();
+    let mut some_unresolved = false;
+    for field in fields {
+        let Some(ty) = field.try_resolve_ref(pool, checker) else {
+            some_unresolved = true;
+            continue;
+        };
+        let field = field.get(pool);
+        let name = field.name.get(pool).to_string();
+        let span = field.span_or_builtin(pool);
+        if let Some(old) = res.iter().find(|f| f.name == name) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Field {name} has already been defined"),
+                span.as_ref()
+            ).note(Note::new_at("Previous definition here", old.decl_span.as_ref())));
+            continue;
+        }
+        res.push(Field {
+            name,
+            ty,
+            has_default: field.default_value.is_some(),
+            accessors: field.accessor_kinds(pool),
+            depends: field.dependencies(pool),
+            decl_span: span,
+        });
+    }
+    (!some_unresolved).then_some(res)
+}
+
+/// Describe already resolved struct fields for macros
+fn reflect_struct_fields(fields: &[StructField], pool: &NodePool) -> Vec<Field> {
+    fields.iter()
+        .map(|field| {
+            let ty = field.resolved_ty(pool).unwrap_or(Ty::Invalid);
+            let field = field.get(pool);
+            Field {
+                name: field.name.get(pool).to_string(),
+                ty,
+                has_default: field.default_value.is_some(),
+                accessors: field.accessor_kinds(pool),
+                depends: field.dependencies(pool),
+                decl_span: field.span_or_builtin(pool),
+            }
+        })
+        .collect()
+}
+
+fn push_type_decl(name: &path::IdentPath, ty: Ty, span: ArcSpan, checker: &mut Checker) {
+    if let Err(old) = checker.scope().types_mut().try_push(name, ty) {
+        let old_span = old.span();
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            format!("Type {name} has already been defined"),
+            span.as_ref()
+        ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+    }
+}
+
+/// Resolve the accessors of the computed properties of the struct `this`
+fn resolve_accessors(fields: &[StructField], this: &Ty, pool: &NodePool, checker: &mut Checker) -> Option<()> {
+    let mut some_unresolved = false;
+    for field in fields {
+        let ty = field.resolved_ty(pool).unwrap_or(Ty::Invalid);
+        let field = field.get(pool);
+        some_unresolved |= checker.with_property(this.clone(), ty, |checker| {
+            let binding = field.binding.try_resolve_ref(pool, checker);
+            let accessors = field.accessor_nodes(pool).try_resolve_ref(pool, checker);
+            binding.and(accessors)
+        }).is_none();
+    }
+    (!some_unresolved).then_some(())
+}
+
+/// A member of a struct body, which is either a field or a declaration 
+/// placed in the namespace of the struct, like `fun init(this) -> bool { ... }`
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "field or declaration")]
+pub enum StructMemberNode {
+    Decl(Decl),
+    Field(StructField),
+}
+
+#[derive(Debug, ParseNode)]
+pub struct StructDeclNode {
+    struct_kw: kw::Struct,
+    name: IdentPath,
+    type_params: Option<TypeParams>,
+    body: delim::Braced<Vec<(StructMember, TerminatingSemicolon)>>,
+    /// Whether the type has been declared on a previous pass
+    #[parse(skip)]
+    declared: bool,
+    /// The scope the type parameters of a generic struct are declared in
+    #[parse(skip)]
+    generics_scope: Option<ScopeID>,
+}
+
+impl StructDeclNode {
+    fn fields(&self, pool: &NodePool) -> Vec<StructField> {
+        self.body.get(pool).value.iter()
+            .filter_map(|m| match *m.0.get(pool) {
+                StructMemberNode::Field(field) => Some(field),
+                StructMemberNode::Decl(_) => None,
+            })
+            .collect()
+    }
+    fn decls(&self, pool: &NodePool) -> Vec<Decl> {
+        self.body.get(pool).value.iter()
+            .filter_map(|m| match *m.0.get(pool) {
+                StructMemberNode::Decl(decl) => Some(decl),
+                StructMemberNode::Field(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl ResolveNode for StructDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        // Extern structs are laid out like regular ones, only their methods 
+        // are provided by the host
+        let is_extern = checker.take_extern();
+        let generics = self.type_params.map(|t| t.get(pool).params(pool)).unwrap_or_default();
+        let name = self.name.get(pool).to_path(pool);
+        let span = self.span_or_builtin(pool);
+        // Generic structs are instantiated where they are used
+        let decl = |ty: Ty| if generics.is_empty() {
+            ty
+        }
+        else {
+            Ty::Generic { params: generics.clone(), ty: ty.into() }
+        };
+        // The struct is forward declared without fields so that its fields 
+        // can refer to it
+        if !self.declared {
+            let ty = Ty::Struct { name: name.to_string(), fields: vec![], decl_span: span.clone(), args: vec![] };
+            push_type_decl(&name, decl(ty), span.clone(), checker);
+            self.declared = true;
+        }
+        let new_generics = self.generics_scope.is_none();
+        let generics_scope = (!generics.is_empty()).then(|| checker.enter_scope(&mut self.generics_scope));
+        for param in generics.iter().filter(|_| new_generics) {
+            push_type_decl(&path::IdentPath::parse(&param.to_string()), param.clone(), param.span(), checker);
+        }
+        let fields = resolve_struct_fields(&self.fields(pool), pool, checker)?;
+        let ty = Ty::Struct {
+            name: name.to_string(),
+            fields,
+            decl_span: span.clone(),
+            args: vec![],
+        };
+        drop(generics_scope);
+        // Replace the forward declaration, unless the name was already taken
+        if checker.scope().types().find(&name).is_some_and(|t| t.span() == span) {
+            checker.scope().types_mut().replace(&name, decl(ty.clone()));
+        }
+        checker.define_struct(decl(ty.clone()), is_extern);
+        // Accessors may refer to the struct itself through `this`, which is 
+        // an instance with the type parameters as its arguments
+        let this = if generics.is_empty() {
+            ty.clone()
+        }
+        else {
+            decl(ty.clone()).instantiate(&generics)
+        };
+        let fields = self.fields(pool);
+        let decls = self.decls(pool);
+        let generics_scope = &mut self.generics_scope;
+        // Declarations in the body are placed in the namespace of the struct. 
+        // Methods of generic structs declare its type parameters themselves
+        checker.with_struct_body(decl(ty), &name, |checker| {
+            let scope = (!generics.is_empty()).then(|| checker.enter_scope(generics_scope));
+            let mut some_unresolved = resolve_accessors(&fields, &this, pool, checker).is_none();
+            drop(scope);
+            for decl in decls {
+                some_unresolved |= decl.try_resolve_ref(pool, checker).is_none();
+            }
+            (!some_unresolved).then_some(())
+        })?;
+        Some(Ty::Void)
+    }
+}
+
+impl EvalNode for StructDeclNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Ok(Value::Void)
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct EnumVariantNode {
+    name: Ident,
+    fields: Option<StructFields>,
+    comma: Option<punct::Comma>,
+}
+
+impl ResolveNode for EnumVariantNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct EnumDeclNode {
+    enum_kw: kw::Enum,
+    name: IdentPath,
+    variants: delim::Braced<Vec<EnumVariant>>,
+}
+
+impl ResolveNode for EnumDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let mut variants: Vec<Variant> = Vec::new();
+        let mut some_unresolved = false;
+        for variant in &self.variants.get(pool).value {
+            let variant = variant.get(pool);
+            let fields = match variant.fields {
+                Some(ref fields) => match resolve_struct_fields(&field_nodes(fields, pool), pool, checker) {
+                    Some(fields) => Some(fields),
+                    None => {
+                        some_unresolved = true;
+                        continue;
+                    }
+                }
+                None => None,
+            };
+            for field in fields.iter().flatten().filter(|f| f.accessors.is_some()) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Field {} of an enum variant can not have accessors", field.name),
+                    field.decl_span.as_ref()
+                ));
+            }
+            let name = variant.name.get(pool).to_string();
+            let span = variant.span_or_builtin(pool);
+            if let Some(old) = variants.iter().find(|v| v.name == name) {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Variant {name} has already been defined"),
+                    span.as_ref()
+                ).note(Note::new_at("Previous definition here", old.decl_span.as_ref())));
+                continue;
+            }
+            variants.push(Variant { name, fields, decl_span: span });
+        }
+        if some_unresolved {
+            return None;
+        }
+
+        let name = self.name.get(pool).to_path(pool);
+        let ty = Ty::Enum {
+            name: name.to_string(),
+            variants: variants.clone(),
+            decl_span: self.span_or_builtin(pool),
+        };
+        // Unit variants are values of the enum type that can be referred to 
+        // as `Enum::Variant`. So are variants with empty braces, which have 
+        // nothing to construct
+        for variant in variants.iter().filter(|v| v.fields.as_ref().is_none_or(Vec::is_empty)) {
+            if let Err(old) = checker.scope().entities_mut().try_push(
+                &name.join(path::Ident::from(variant.name.as_str())),
+                Entity::new(ty.clone(), variant.decl_span.clone(), false)
+            ) {
+                let old_span = old.span();
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Name {name}::{} has already been defined", variant.name),
+                    variant.decl_span.as_ref()
+                ).note(Note::new_at("Previous definition here", old_span.as_ref())));
+            }
+        }
+        push_type_decl(&name, ty, self.span_or_builtin(pool), checker);
+        Some(Ty::Void)
+    }
+}
+
+impl EvalNode for EnumDeclNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Ok(Value::Void)
+    }
+}
+
+/// A type provided by the host whose fields are unknown, such as 
+/// `extern decl CCScene`. It can only be used through extern functions
+#[derive(Debug, ParseNode)]
+pub struct OpaqueDeclNode {
+    decl_kw: kw::Decl,
+    name: IdentPath,
+}
+
+impl ResolveNode for OpaqueDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let is_extern = checker.take_extern();
+        let name = self.name.get(pool).to_path(pool);
+        let ty = Ty::Struct {
+            name: name.to_string(),
+            fields: vec![],
+            decl_span: self.span_or_builtin(pool),
+            args: vec![],
+        };
+        push_type_decl(&name, ty.clone(), self.span_or_builtin(pool), checker);
+        checker.define_struct(ty, is_extern);
+        Some(Ty::Void)
+    }
+}
+
+impl EvalNode for OpaqueDeclNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Ok(Value::Void)
+    }
+}
+
+#[derive(Debug, ParseNode, ResolveNode, EvalNode)]
+#[parse(expected = "extern declaration")]
+pub enum ExternItemNode {
+    LetDecl(LetDecl),
+    FunDecl(FunDecl),
+    StructDecl(StructDecl),
+    OpaqueDecl(OpaqueDecl),
+}
+
+/// A declaration that is provided by the host instead of being compiled, 
+/// such as `extern fun print(msg: string) -> void`
+#[derive(Debug, ParseNode)]
+pub struct ExternDeclNode {
+    extern_kw: kw::Extern,
+    item: ExternItem,
+}
+
+impl ResolveNode for ExternDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        checker.with_extern(true, |checker| self.item.try_resolve_ref(pool, checker))
+    }
+}
+
+impl EvalNode for ExternDeclNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Ok(Value::Void)
+    }
+}
+
+/// A declaration marked as exported from its module, such as 
+/// `public extern fun print(msg: string) -> void`. Visibility isn't 
+/// enforced yet: every item can be used from other modules, so this is 
+/// only accepted as syntax and checks the same as the declaration
+#[derive(Debug, ParseNode)]
+pub struct PublicDeclNode {
+    public_kw: kw::Public,
+    decl: Decl,
+}
+
+impl ResolveNode for PublicDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.decl.try_resolve_ref(pool, checker)
+    }
+}
+
+impl EvalNode for PublicDeclNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        self.decl.eval_ref(pool, eval)
+    }
+}
+
+/// A decorator applied to a declaration, such as `@override(priority: 5)`. 
+/// Decorators are macros in the decorator namespace whose first parameter 
+/// is the declaration and the rest are the arguments of the decorator
+#[derive(Debug, ParseNode)]
+pub struct DecoratorNode {
+    name: IdentPath,
+    args: Option<ArgList>,
+    /// The macro this decorator runs
+    #[parse(skip)]
+    entity: Option<Entity>,
+}
+
+impl DecoratorNode {
+    pub(crate) fn parse_with(
+        name: IdentPath,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            name,
+            args: ArgList::peek_and_parse(pool, src, tokenizer)?,
+            entity: None,
+        };
+        Ok(pool.add(res))
+    }
+}
+
+impl ResolveNode for DecoratorNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let name = self.name.get(pool).to_path(pool);
+        let entity = checker.scopes().find_map(|s| s.entities().find(&name).cloned())?;
+        let ty = entity.ty();
+        let params = match ty.reduce() {
+            Ty::Function { params, ret_ty: _ } if !params.is_empty() => params[1..].to_vec(),
+            _ => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot use a value of type {ty} as a decorator"),
+                    self.name.get(pool).span_or_builtin(pool).as_ref()
+                ).note(Note::new(
+                    "Decorators are macros that take the declaration they are applied to \
+                    as their first parameter", true
+                )));
+                return Some(Ty::Invalid);
+            }
+        };
+        if !entity.is_macro() {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("{name} is not a macro"),
+                self.name.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                "Functions used as decorators must be declared with 'macro' so they can \
+                be run at compile time", true
+            )));
+        }
+        else {
+            self.entity = Some(entity.clone());
+        }
+        let args = match self.args {
+            Some(ref args) => resolve_args(args, &params, pool, checker)?,
+            None => Vec::new(),
+        };
+        check_args(&params, &args, entity.optional_params(), self.span(pool), checker);
+        Some(ty)
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        logger.lock().unwrap().log(Message::new(
+            Level::Error,
+            format!("Unknown decorator {}", self.name.get(pool).to_path(pool)),
+            self.name.get(pool).span_or_builtin(pool).as_ref()
+        ));
+    }
+}
+
+/// A declaration with decorators applied to it, such as 
+/// `@modify struct MenuLayer { ... }`
+#[derive(Debug)]
+pub struct DecoratedDeclNode {
+    decorators: Vec<Decorator>,
+    decl: Decl,
+    /// Where the declaration was checked, which is where the code generated 
+    /// by its macros is checked too
+    context: Option<Context>,
+    /// Whether the macros of the decorators have been run
+    expanded: bool,
+    /// The code generated by the macros
+    generated: Vec<Generated>,
+}
+pub type DecoratedDecl = RefToNode<DecoratedDeclNode>;
+
+impl DecoratedDeclNode {
+    /// Parse the rest of a decorated declaration after its first decorator
+    pub(crate) fn parse_with(
+        first: Decorator,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let mut decorators = vec![first];
+        while Self::peek(0, tokenizer) {
+            decorators.push(Decorator::parse_ref(pool, src.clone(), tokenizer)?);
+        }
+        let res = Self {
+            decorators,
+            decl: Decl::parse_ref(pool, src, tokenizer)?,
+            context: None,
+            expanded: false,
+            generated: vec![],
+        };
+        Ok(pool.add(res))
+    }
+    /// Whether the tokens after the name of a decorator mean that it is 
+    /// being applied to a declaration, rather than used as a value
+    pub(crate) fn peek_applied(tokenizer: &TokenIterator) -> bool {
+        let decl_follows = |pos| Decl::peek(pos, tokenizer);
+        decl_follows(0) || (ArgList::peek(0, tokenizer) && decl_follows(1))
+    }
+}
+
+impl Node for DecoratedDeclNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.decorators, &self.decl]
+    }
+}
+
+impl ParseNode for DecoratedDeclNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        let first = Decorator::parse_ref(pool, src.clone(), tokenizer)?;
+        Self::parse_with(first, pool, src, tokenizer)
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        // `@(...)` is a splice rather than a decorator
+        punct::At::peek(pos, tokenizer) && Ident::peek(pos + 1, tokenizer)
+    }
+}
+
+impl DecoratedDeclNode {
+    /// Run the macros of the decorators on the declaration, passing the 
+    /// result of each one to the next, and apply the final result to the 
+    /// declaration. Returns the code generated by the macros
+    pub(crate) fn run_macros(&self, pool: &NodePool, eval: &mut Evaluator) -> Vec<Code> {
+        let mut generated = vec![];
+        let Some(mut target) = self.decl.get(pool).reflect(pool) else {
+            return generated;
+        };
+        // The decorator whose macro produced `target`, if any
+        let mut produced_by = None;
+        for decorator in &self.decorators {
+            let decorator = decorator.get(pool);
+            let Some(ref entity) = decorator.entity else {
+                continue;
+            };
+            let name = decorator.name.get(pool).to_path(pool).to_string();
+            let span = decorator.span_or_builtin(pool);
+            let res = eval.invoke(name.clone(), span.clone(), |eval| {
+                let fun = eval.entity_value(&name, Some(entity), &span)?;
+                let mut args = match decorator.args {
+                    Some(ref args) => eval_args(args, pool, eval)?,
+                    None => Vec::new(),
+                };
+                args.insert(0, (None, Value::Reflect(target.clone().into())));
+                match fun {
+                    Value::Function(fun) => eval.call(pool, fun, args, span.clone()),
+                    other => Err(Interrupt::Error(format!("Cannot call a value of type {}", other.kind()), span.clone())),
+                }
+            });
+            match res {
+                // Generated code is added next to the declaration, which is 
+                // passed on to the next macro as it is
+                Some(Value::Reflect(value)) if matches!(*value, Reflect::Code(_)) => {
+                    let Reflect::Code(code) = *value else { unreachable!() };
+                    generated.push(code);
+                }
+                Some(Value::Reflect(value)) => {
+                    target = *value;
+                    produced_by = Some((name, span));
+                }
+                Some(_) => {}
+                // Later macros would see a declaration that wasn't 
+                // transformed correctly, so nothing is applied
+                None => return vec![],
+            }
+        }
+        let Some((name, span)) = produced_by else {
+            return generated;
+        };
+        if let Err(msg) = self.decl.get(pool).apply_reflect(pool, target) {
+            eval.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("The result of macro {name} can not be applied: {msg}"),
+                span.as_ref()
+            ).note(Note::new_at(
+                "Declaration here",
+                self.decl.get(pool).span_or_builtin(pool).as_ref()
+            )));
+        }
+        generated
+    }
+    /// Mark the macros of the decorators as run, returning whether they had 
+    /// been run already
+    pub(crate) fn mark_expanded(&mut self) -> bool {
+        std::mem::replace(&mut self.expanded, true)
+    }
+    pub(crate) fn context(&self) -> Option<Context> {
+        self.context.clone()
+    }
+    pub(crate) fn add_generated(&mut self, code: Generated) {
+        self.generated.push(code);
+    }
+}
+
+impl ResolveNode for DecoratedDeclNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.context = Some(checker.context());
+        let ty = self.decl.try_resolve_ref(pool, checker);
+        try_resolve_list!(&self.decorators, (pool, checker), d => d => d);
+        // Macros receive a description of the declaration as their first 
+        // argument
+        let kind = self.decl.get(pool).reflect_kind(pool);
+        for decorator in &self.decorators {
+            let decorator = decorator.get(pool);
+            let Some(Ty::Function { params, ret_ty }) = decorator.entity.as_ref().map(|e| e.ty()) else {
+                continue;
+            };
+            let Some((_, param)) = params.first() else {
+                continue;
+            };
+            let name = decorator.name.get(pool).to_path(pool);
+            let expected = reflect::reflect_ty(kind);
+            let msg = if !param.is_unreal() && *param != expected {
+                format!("Macro {name} takes a {param}, but is applied to a Reflect::{kind}")
+            }
+            // The result of the macro replaces the declaration, or is code 
+            // to add next to it
+            else if !ret_ty.is_unreal() && ![Ty::Void, expected, reflect::reflect_ty("Code")].contains(&*ret_ty) {
+                format!("Macro {name} returns a {ret_ty}, which can not replace a Reflect::{kind}")
+            }
+            else {
+                continue;
+            };
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                msg,
+                decorator.span_or_builtin(pool).as_ref()
+            ).note(Note::new_at(
+                "Declaration here",
+                self.decl.get(pool).span_or_builtin(pool).as_ref()
+            )));
+        }
+        ty
+    }
+}
+
+impl EvalNode for DecoratedDeclNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        // Macros are run once before the rest of the code by `run_macros`
+        Ok(Value::Void)
+    }
+}
+
+#[derive(Debug, ParseNode, ResolveNode, EvalNode)]
+#[parse(expected = "item declaration")]
+pub enum DeclNode {
+    LetDecl(LetDecl),
+    FunDecl(FunDecl),
+    StructDecl(StructDecl),
+    EnumDecl(EnumDecl),
+    ExternDecl(ExternDecl),
+    PublicDecl(PublicDecl),
+    DecoratedDecl(DecoratedDecl),
+}
+
+impl DeclNode {
+    /// The name of the type in the `Reflect` namespace that describes this 
+    /// declaration
+    pub(crate) fn reflect_kind(&self, pool: &NodePool) -> &'static str {
+        match self {
+            Self::LetDecl(_) => "Variable",
+            Self::FunDecl(_) => "Function",
+            Self::StructDecl(_) => "Struct",
+            Self::EnumDecl(_) => "Enum",
+            Self::ExternDecl(decl) => match *decl.get(pool).item.get(pool) {
+                ExternItemNode::LetDecl(_) => "Variable",
+                ExternItemNode::FunDecl(_) => "Function",
+                ExternItemNode::StructDecl(_) | ExternItemNode::OpaqueDecl(_) => "Struct",
+            },
+            Self::PublicDecl(decl) => decl.get(pool).decl.get(pool).reflect_kind(pool),
+            Self::DecoratedDecl(decl) => decl.get(pool).decl.get(pool).reflect_kind(pool),
+        }
+    }
+    /// Describe this declaration for the macros applied to it. The 
+    /// declaration must have been resolved
+    pub(crate) fn reflect(&self, pool: &NodePool) -> Option<Reflect> {
+        Some(match self {
+            Self::LetDecl(decl) => reflect_let(*decl, pool),
+            Self::FunDecl(decl) => reflect_fun(*decl, pool)?,
+            Self::StructDecl(decl) => {
+                let decl = decl.get(pool);
+                Reflect::Struct {
+                    name: decl.name.get(pool).to_path(pool).to_string(),
+                    fields: reflect_struct_fields(&decl.fields(pool), pool),
+                }
+            }
+            Self::EnumDecl(decl) => {
+                let decl = decl.get(pool);
+                Reflect::Enum {
+                    name: decl.name.get(pool).to_path(pool).to_string(),
+                    variants: decl.variants.get(pool).value.iter()
+                        .map(|v| {
+                            let v = v.get(pool);
+                            Variant {
+                                name: v.name.get(pool).to_string(),
+                                fields: v.fields.as_ref().map(|f| reflect_struct_fields(&field_nodes(f, pool), pool)),
+                                decl_span: v.span_or_builtin(pool),
+                            }
+                        })
+                        .collect(),
+                }
+            }
+            Self::ExternDecl(decl) => match *decl.get(pool).item.get(pool) {
+                ExternItemNode::LetDecl(decl) => reflect_let(decl, pool),
+                ExternItemNode::FunDecl(decl) => reflect_fun(decl, pool)?,
+                ExternItemNode::StructDecl(decl) => {
+                    let decl = decl.get(pool);
+                    Reflect::Struct {
+                        name: decl.name.get(pool).to_path(pool).to_string(),
+                        fields: reflect_struct_fields(&decl.fields(pool), pool),
+                    }
+                }
+                ExternItemNode::OpaqueDecl(decl) => Reflect::Struct {
+                    name: decl.get(pool).name.get(pool).to_path(pool).to_string(),
+                    fields: vec![],
+                },
+            },
+            Self::PublicDecl(decl) => decl.get(pool).decl.get(pool).reflect(pool)?,
+            Self::DecoratedDecl(decl) => decl.get(pool).decl.get(pool).reflect(pool)?,
+        })
+    }
+    /// Apply the result of a macro to this declaration. Macros can not 
+    /// modify the values they are given yet, so the result has to describe 
+    /// this declaration as it is
+    pub(crate) fn apply_reflect(&self, pool: &NodePool, result: Reflect) -> Result<(), String> {
+        let Some(current) = self.reflect(pool) else {
+            return Ok(());
+        };
+        if result.kind() != current.kind() {
+            return Err(format!("expected a {}, got a {}", current.type_name(), result.type_name()));
+        }
+        if result != current {
+            return Err(format!("it describes a different {}", current.type_name()));
+        }
+        Ok(())
+    }
+}
+
+fn reflect_let(decl: LetDecl, pool: &NodePool) -> Reflect {
+    let decl = decl.get(pool);
+    let ty = decl.ty.map(|t| t.1.resolved_ty(pool))
+        .or(decl.value.map(|v| v.1.resolved_ty(pool)))
+        .flatten()
+        .unwrap_or(Ty::Invalid);
+    Reflect::Variable {
+        name: decl.name.get(pool).to_path(pool).to_string(),
+        ty,
+        value: decl.value.map(|v| v.1),
+    }
+}
+
+fn reflect_fun(decl: FunDecl, pool: &NodePool) -> Option<Reflect> {
+    let Some(Ty::Function { params, ret_ty }) = decl.resolved_ty(pool) else {
+        return None;
+    };
+    Some(Reflect::Function {
+        name: decl.get(pool).name.map(|n| n.get(pool).to_name(pool)).unwrap_or_default(),
+        params,
+        ret_ty: *ret_ty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::{check_errors, check_warnings};
+
+    const LAYOUT: &str = "enum Layout {\n    Row {}\n    Column\n    Grid { columns: int; }\n}\n";
+
+    #[test]
+    fn enum_variants() {
+        assert_eq!(
+            check_errors(&format!(
+                "{LAYOUT}fun test(a: int) -> Layout {{\n    let g = Layout::Grid {{ columns: a }};\n    \
+                let c = Layout::Column;\n    Layout::Row\n}}\n"
+            )),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn unknown_enum_variant() {
+        assert_eq!(
+            check_errors(&format!("{LAYOUT}fun test(a: int) -> Layout {{\n    Layout::Diagonal\n}}\n")),
+            ["Unknown item Layout::Diagonal"]
+        );
+    }
+
+    #[test]
+    fn extern_declarations() {
+        assert_eq!(
+            check_errors(
+                "public extern fun print(msg: string) -> void;\nextern decl CCScene;\n\
+                extern fun currentScene() -> CCScene;\n\
+                public extern struct CCPoint {\n    x: float;\n    y: float;\n}\n\
+                extern let winSize: CCPoint;\n\
+                fun test() -> void {\n    print(\"hi\");\n    let s = currentScene();\n    let w = winSize.x;\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn function_without_body() {
+        assert_eq!(check_errors("fun add(a: int, b: int) -> int;\n"), ["Function has no body"]);
+    }
+
+    const TAG: &str = "macro @tag(this: Reflect::Variable, priority: int) -> Reflect::Variable => this;\n";
+
+    #[test]
+    fn decorators() {
+        assert_eq!(
+            check_errors(&format!("{TAG}@tag(priority: 1)\nlet a = 1;\n@tag(priority: 5)\nlet b = 2;\n")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn decorator_that_is_not_a_macro() {
+        assert_eq!(
+            check_errors("fun @plain(this: Reflect::Variable) -> Reflect::Variable => this;\n@plain\nlet a = 1;\n"),
+            ["@plain is not a macro"]
+        );
+    }
+
+    #[test]
+    fn macros_run_on_declarations() {
+        assert_eq!(
+            check_errors("macro @deny(this: Reflect::Variable) {\n    Reflect::error(this.name);\n}\n@deny\nlet a = 1;\n"),
+            ["a"]
+        );
+    }
+
+    #[test]
+    fn macro_applied_to_wrong_declaration() {
+        assert_eq!(
+            check_errors(&format!("{TAG}@tag(priority: 1)\nfun f(b: int) -> int => b;\n")),
+            ["Macro @tag takes a Reflect::Variable, but is applied to a Reflect::Function"]
+        );
+    }
+
+    #[test]
+    fn macro_result_of_wrong_kind() {
+        assert_eq!(
+            check_errors("macro @value(this: Reflect::Variable) -> Reflect::Expr => this.value();\n@value\nlet a = 1;\n"),
+            ["Macro @value returns a Reflect::Expr, which can not replace a Reflect::Variable"]
+        );
+    }
+
+    #[test]
+    fn decorator_argument_of_wrong_type() {
+        assert_eq!(
+            check_errors(&format!("{TAG}@tag(priority: \"high\")\nlet a = 1;\n")),
+            ["Cannot convert from type string to int"]
+        );
+        assert_eq!(check_errors(&format!("{TAG}@tag\nlet a = 1;\n")), ["Missing arguments"]);
+    }
+
+    #[test]
+    fn unknown_decorator() {
+        assert_eq!(check_errors("@missing\nlet a = 1;\n"), ["Unknown decorator @missing"]);
+    }
+
+    #[test]
+    fn methods_in_struct_body() {
+        assert_eq!(
+            check_errors(
+                "struct Counter {\n    value: int;\n    fun get(this) -> int => this.value;\n}\n\
+                fun test(c: Counter) -> int => c.get();\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn this_outside_struct_body() {
+        assert_eq!(
+            check_errors("fun get(this) -> int => 1;\n"),
+            ["Cannot infer the type of 'this'"]
+        );
+    }
+
+    const NODE: &str = "struct Pos {\n    x: int;\n}\n\
+        struct Node {\n    pos: Pos;\n    \
+        x: int {\n        set(value) => this.pos.x = value;\n        get => this.pos.x;\n    }\n    \
+        double: int {\n        get => this.x * 2;\n    }\n}\n";
+
+    #[test]
+    fn computed_properties() {
+        assert_eq!(
+            check_errors(&format!(
+                "{NODE}fun test(n: Node) -> int {{\n    n.x = n.double;\n    n.x\n}}\n\
+                fun make() -> Node => Node {{ pos: Pos {{ x: 1 }} }};\n"
+            )),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn property_without_setter() {
+        assert_eq!(
+            check_errors(&format!("{NODE}fun test(n: Node) {{\n    n.double = 2;\n}}\n")),
+            ["Property 'double' has no setter"]
+        );
+        assert_eq!(
+            check_errors("struct Node {\n    x: int {\n        set(value) => 1;\n    }\n}\n"),
+            ["Property x must have a getter"]
+        );
+    }
+
+    #[test]
+    fn accessor_of_wrong_type() {
+        assert_eq!(
+            check_errors("struct Node {\n    x: int {\n        get => true;\n        set(value) => value;\n    }\n}\n"),
+            ["Cannot convert from type bool to int"]
+        );
+    }
+
+    const SIZE: &str = "struct Size {\n    width: int;\n    height: int;\n}\n";
+
+    #[test]
+    fn bound_properties() {
+        let code = format!(
+            "{SIZE}struct Node {{\n    width: int;\n    height: int;\n    size: Size <=> {{ width, height }};\n}}\n\
+            fun test(n: Node, s: Size) -> int {{\n    n.size = s;\n    n.size.width\n}}\n"
+        );
+        assert_eq!(check_errors(&code), Vec::<String>::new());
+        assert_eq!(check_warnings(&code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn binding_to_wrong_fields() {
+        assert_eq!(
+            check_errors(&format!(
+                "{SIZE}struct Node {{\n    width: int;\n    depth: int;\n    size: Size <=> {{ width, depth }};\n}}\n"
+            )),
+            ["Type Size has no field 'depth'", "Binding to Size is missing fields 'height'"]
+        );
+        assert_eq!(
+            check_errors(&format!(
+                "{SIZE}struct Node {{\n    width: int;\n    height: bool;\n    size: Size <=> {{ width, height }};\n}}\n"
+            )),
+            ["Cannot bind field 'height' of type bool to field of type int"]
+        );
+    }
+
+    const AREA: &str = "struct Rect {\n    width: int;\n    height: int;\n    \
+        area: int {\n        depends width;\n        depends height;\n        get => this.width * this.height;\n    }\n}\n";
+
+    #[test]
+    fn dependencies() {
+        assert_eq!(check_errors(AREA), Vec::<String>::new());
+        assert_eq!(
+            check_errors(&format!(
+                "macro @deps(this: Reflect::Struct) {{\n    \
+                let area = this.field(2);\n    Reflect::error(area.dependency(area.dependencyCount - 1));\n}}\n\
+                @deps\n{AREA}"
+            )),
+            ["height"]
+        );
+        assert_eq!(
+            check_errors(
+                "struct Rect {\n    width: int;\n    \
+                area: int {\n        depends depth;\n        depends area;\n        get => this.width;\n    }\n}\n"
+            ),
+            ["Property area can not depend on itself", "Unknown field 'depth' on Rect"]
+        );
+    }
+
+    #[test]
+    fn reflected_fields_and_refs() {
+        assert_eq!(
+            check_errors(&format!(
+                "macro @refs(this: Reflect::Struct) {{\n    \
+                for field in this.fields {{\n        for use in Reflect::findRefs(field) {{\n            \
+                Reflect::error(field.name + \": \" + use.source);\n        }}\n    }}\n}}\n\
+                @refs\n{AREA}"
+            )),
+            ["width: this.width", "height: this.height"]
+        );
+        assert_eq!(
+            check_errors("macro @refs(this: Reflect::Struct) {\n    let refs: [Reflect::Field] = Reflect::findRefs(this.field(0));\n}\n"),
+            ["Cannot convert from type [Reflect::Expr] to [Reflect::Field]"]
+        );
+    }
+
+    const BOX: &str = "struct Box<T> {\n    value: T;\n    fun get<T>(this) -> T => this.value;\n}\n\
+        fun Box::or<T>(this, other: T) -> T => this.value;\n";
+
+    #[test]
+    fn generics() {
+        assert_eq!(
+            check_errors(&format!(
+                "{BOX}fun first<T>(list: [T]) -> T => list[0];\n\
+                fun test(b: Box<int>) -> int {{\n    let c: Box<string> = Box {{ value: \"c\" }};\n    \
+                first([1, 2]) + first::<int>([3]) + b.get() + b.or(2) + [1].map((x) => x * 2)[0]\n}}\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!(
+                "{BOX}fun add<T>(a: T) -> T => a + 1;\nfun empty<T>() -> [T] => [];\n\
+                fun test(a: Box, b: int<string>, c: Box<int>) {{\n    let e = empty();\n    let s: string = c.get();\n}}\n"
+            )),
+            [
+                "Generic type Box<T> must be given type arguments",
+                "Type int is not generic",
+                "Cannot infer the type parameter T",
+                "Cannot convert from type int to string",
+                "Cannot use operator '+' on types T and int",
+            ]
+        );
+        assert_eq!(
+            check_errors(
+                "struct Node<T> {\n    value: T;\n    next: Node<T>?;\n}\n\
+                fun valueOf<T>(node: Node<T>) -> T => node.value;\n\
+                fun test(n: Node<int>) -> Node<int> {\n    let a: int = valueOf(n) + valueOf(Node { value: 2, next: none });\n    \
+                Node { value: a, next: n }\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+    }
+}
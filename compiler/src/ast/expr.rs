@@ -1,7 +1,7 @@
 
 use std::sync::Arc;
 
-use dash_macros::{ParseNode, ResolveNode};
+use dash_macros::{ParseNode, ResolveNode, EvalNode};
 use crate::{
     parser::{
         parse::{
@@ -11,42 +11,85 @@ use crate::{
         tokenizer::TokenIterator
     },
     shared::src::Src,
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::Ty, path}, try_resolve_list
+    checker::{resolve::{ResolveNode, ResolveRef}, coherency::{Checker, ScopeID}, ty::Ty, path}, try_resolve_list,
+    eval::{EvalNode, EvalRef, EvalResult, Evaluator, value::Value}
 };
 use super::{
-    decl::Decl,
-    token::{Ident, punct::{self, TerminatingSemicolon}, op::{Prec, self}, delim},
-    atom::Atom,
+    decl::{Decl, DeclNode, DecoratorNode, DecoratedDeclNode},
+    token::{Ident, punct::{self, TerminatingSemicolon}, op::{Prec, self}, delim, kw},
+    atom::{Atom, AtomNode, ItemUseNode, Construct, ConstructNode},
     flow::Flow,
-    ops::{BinOp, UnOp, Call, Index, CallNode, IndexNode, UnOpNode, BinOpNode}
+    ty::TypeArgs,
+    ops::{
+        BinOp, UnOp, Call, Index, Member, Unwrap, Is, Cast,
+        CallNode, IndexNode, MemberNode, UnwrapNode, IsNode, CastNode, UnOpNode, BinOpNode
+    }
 };
 
 #[derive(Debug, ParseNode)]
 #[parse(expected = "identifier")]
 pub enum IdentComponentNode {
-    Attribute(punct::At, Ident),
+    Attribute(punct::At, #[parse(peek_point)] Ident),
     Ident(Ident),
 }
 
+impl IdentComponentNode {
+    pub(crate) fn to_ident(&self, pool: &NodePool) -> path::Ident {
+        path::Ident::from(match self {
+            Self::Ident(i) => i.get(pool).to_string(),
+            Self::Attribute(_, i) => format!("@{}", i.get(pool)),
+        })
+    }
+}
+
 impl ResolveNode for IdentComponentNode {
     fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
         Some(Ty::Invalid)
     }
 }
 
+/// The `::` between the components of a path. A `::` followed by `<` 
+/// instead starts the type arguments of a generic item, like `id::<int>`
+#[derive(Debug)]
+pub struct PathSeparatorNode {
+    sep: punct::Namespace,
+}
+pub type PathSeparator = RefToNode<PathSeparatorNode>;
+
+impl Node for PathSeparatorNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.sep]
+    }
+}
+
+impl ParseNode for PathSeparatorNode {
+    fn parse_node(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            sep: ParseRef::parse_ref(pool, src, tokenizer)?,
+        };
+        Ok(pool.add(res))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        punct::Namespace::peek(pos, tokenizer) && !op::Less::peek(pos + 1, tokenizer)
+    }
+}
+
+impl ResolveNode for PathSeparatorNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
 #[derive(Debug, ParseNode)]
 pub struct IdentPathNode {
     absolute: Option<punct::Namespace>,
-    path: Separated<IdentComponent, punct::Namespace>,
+    path: Separated<IdentComponent, PathSeparator>,
 }
 
 impl IdentPathNode {
     pub(crate) fn to_path(&self, pool: &NodePool) -> path::IdentPath {
         path::IdentPath::new(
-            self.path.iter().map(|i| path::Ident::from(match *i.get(pool) {
-                IdentComponentNode::Ident(i) => i.get(pool).to_string(),
-                IdentComponentNode::Attribute(_, i) => format!("@{}", i.get(pool)),
-            })).collect::<Vec<_>>(),
+            self.path.iter().map(|i| i.get(pool).to_ident(pool)).collect::<Vec<_>>(),
             self.absolute.is_some()
         )
     }
@@ -58,7 +101,7 @@ impl ResolveNode for IdentPathNode {
     }
 }
 
-#[derive(Debug, ParseNode, ResolveNode)]
+#[derive(Debug, ParseNode, ResolveNode, EvalNode)]
 #[parse(expected = "expression")]
 pub enum ScalarExprNode {
     Decl(Decl),
@@ -66,23 +109,85 @@ pub enum ScalarExprNode {
     Atom(Atom),
 }
 
-#[derive(Debug, ResolveNode)]
+#[derive(Debug)]
 pub enum ExprNode {
     BinOp(BinOp),
     UnOp(UnOp),
     Call(Call),
     Index(Index),
+    Member(Member),
+    Unwrap(Unwrap),
+    Is(Is),
+    Cast(Cast),
+    Construct(Construct),
     Scalar(ScalarExpr),
 }
 pub type Expr = RefToNode<ExprNode>;
 
 impl ExprNode {
+    fn parse_scalar(
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<Self, FatalParseError> {
+        // `name: Type { ... }` binds the constructed value to a name
+        if tokenizer.allow_constructs() && Ident::peek(0, tokenizer) && punct::Colon::peek(1, tokenizer) {
+            let binding = Some(ParseRef::parse_ref(pool, src.clone(), tokenizer)?);
+            let path = IdentPath::parse_ref(pool, src.clone(), tokenizer)?;
+            let type_args = Self::parse_type_args(pool, src.clone(), tokenizer)?;
+            return Ok(Self::Construct(RefToNode::new_raw(
+                ConstructNode::parse_with(binding, path, type_args, pool, src, tokenizer)?
+            )));
+        }
+        // Declarations may start with a contextual keyword, like 
+        // `commutative fun`
+        if !IdentPath::peek(0, tokenizer) || Decl::peek(0, tokenizer) {
+            return Ok(Self::Scalar(ParseRef::parse_ref(pool, src, tokenizer)?));
+        }
+        // A path followed by braces is a construction. The path has to be 
+        // parsed first to find that out, since it can be arbitrarily long
+        let path = IdentPath::parse_ref(pool, src.clone(), tokenizer)?;
+        // `@decorator` is only applied to a declaration if one follows it, 
+        // otherwise it refers to the decorator as a value
+        let is_decorator = matches!(
+            path.get(pool).to_path(pool).split_last(),
+            Some((_, path::Ident::Decorator(_)))
+        );
+        if is_decorator && DecoratedDeclNode::peek_applied(tokenizer) {
+            let decorator = RefToNode::new_raw(DecoratorNode::parse_with(path, pool, src.clone(), tokenizer)?);
+            let decl = RefToNode::new_raw(DecoratedDeclNode::parse_with(decorator, pool, src, tokenizer)?);
+            let decl = RefToNode::new(pool, DeclNode::DecoratedDecl(decl));
+            return Ok(Self::Scalar(RefToNode::new(pool, ScalarExprNode::Decl(decl))));
+        }
+        let type_args = Self::parse_type_args(pool, src.clone(), tokenizer)?;
+        if tokenizer.allow_constructs() && delim::Braced::<delim::P>::peek(0, tokenizer) {
+            return Ok(Self::Construct(RefToNode::new_raw(
+                ConstructNode::parse_with(None, path, type_args, pool, src, tokenizer)?
+            )));
+        }
+        let item = RefToNode::new(pool, ItemUseNode::Ident(path, type_args, None));
+        let atom = RefToNode::new(pool, AtomNode::ItemUse(item));
+        Ok(Self::Scalar(RefToNode::new(pool, ScalarExprNode::Atom(atom))))
+    }
+    /// Parse the explicit type arguments of a generic item, like the 
+    /// `::<int>` in `id::<int>(5)`
+    fn parse_type_args(
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<Option<TypeArgs>, FatalParseError> {
+        if !punct::Namespace::peek(0, tokenizer) {
+            return Ok(None);
+        }
+        let _ = punct::Namespace::parse_ref(pool, src.clone(), tokenizer)?;
+        TypeArgs::parse_ref(pool, src, tokenizer).map(Some)
+    }
     fn parse_postfix(
         pool: &mut NodePool,
         src: Arc<Src>,
         tokenizer: &mut TokenIterator
     ) -> Result<NodeID, FatalParseError> {
-        let mut expr = Self::Scalar(ParseRef::parse_ref(pool, src.clone(), tokenizer)?);
+        let mut expr = Self::parse_scalar(pool, src.clone(), tokenizer)?;
         loop {
             if delim::Parenthesized::<delim::P>::peek(0, tokenizer) {
                 expr = Self::Call(RefToNode::new_raw(
@@ -94,6 +199,18 @@ impl ExprNode {
                     IndexNode::parse_with(RefToNode::new_raw(pool.add(expr)), pool, src.clone(), tokenizer)?
                 ));
             }
+            else if punct::Dot::peek(0, tokenizer) ||
+                op::Question::peek(0, tokenizer) && punct::Dot::peek(1, tokenizer)
+            {
+                expr = Self::Member(RefToNode::new_raw(
+                    MemberNode::parse_with(RefToNode::new_raw(pool.add(expr)), pool, src.clone(), tokenizer)?
+                ));
+            }
+            else if op::Not::peek(0, tokenizer) {
+                expr = Self::Unwrap(RefToNode::new_raw(
+                    UnwrapNode::parse_with(RefToNode::new_raw(pool.add(expr)), pool, src.clone(), tokenizer)?
+                ));
+            }
             else {
                 break;
             }
@@ -115,6 +232,31 @@ impl ExprNode {
             Self::parse_postfix(pool, src, tokenizer)
         }
     }
+    /// Parse type tests and casts like `value is Type` and `value as Type`
+    fn parse_type_ops(
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let mut expr = Self::parse_unop(pool, src.clone(), tokenizer)?;
+        loop {
+            let op = if kw::Is::peek(0, tokenizer) {
+                Self::Is(RefToNode::new_raw(
+                    IsNode::parse_with(RefToNode::new_raw(expr), pool, src.clone(), tokenizer)?
+                ))
+            }
+            else if kw::As::peek(0, tokenizer) {
+                Self::Cast(RefToNode::new_raw(
+                    CastNode::parse_with(RefToNode::new_raw(expr), pool, src.clone(), tokenizer)?
+                ))
+            }
+            else {
+                break;
+            };
+            expr = pool.add(op);
+        }
+        Ok(expr)
+    }
     fn parse_binop_prec<F>(
         prec: Prec, sides: &mut F,
         pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator
@@ -142,18 +284,60 @@ impl Node for ExprNode {
             Self::UnOp(unop) => vec![unop],
             Self::Call(call) => vec![call],
             Self::Index(index) => vec![index],
+            Self::Member(member) => vec![member],
+            Self::Unwrap(unwrap) => vec![unwrap],
+            Self::Is(is) => vec![is],
+            Self::Cast(cast) => vec![cast],
+            Self::Construct(construct) => vec![construct],
             Self::Scalar(scalar) => vec![scalar],
         }
     }
 }
 
+impl ResolveNode for ExprNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        // The operands of an operation don't have the type the whole 
+        // expression is expected to have. Calls use it to infer the type 
+        // parameters of generic functions
+        match self {
+            Self::BinOp(binop) => checker.with_expected_ty(None, |c| binop.try_resolve_ref(pool, c)),
+            Self::UnOp(unop) => checker.with_expected_ty(None, |c| unop.try_resolve_ref(pool, c)),
+            Self::Call(call) => call.try_resolve_ref(pool, checker),
+            Self::Index(index) => checker.with_expected_ty(None, |c| index.try_resolve_ref(pool, c)),
+            Self::Member(member) => checker.with_expected_ty(None, |c| member.try_resolve_ref(pool, c)),
+            Self::Unwrap(unwrap) => checker.with_expected_ty(None, |c| unwrap.try_resolve_ref(pool, c)),
+            Self::Is(is) => checker.with_expected_ty(None, |c| is.try_resolve_ref(pool, c)),
+            Self::Cast(cast) => checker.with_expected_ty(None, |c| cast.try_resolve_ref(pool, c)),
+            Self::Construct(construct) => construct.try_resolve_ref(pool, checker),
+            Self::Scalar(scalar) => scalar.try_resolve_ref(pool, checker),
+        }
+    }
+}
+
+impl EvalNode for ExprNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        match self {
+            Self::BinOp(binop) => binop.eval_ref(pool, eval),
+            Self::UnOp(unop) => unop.eval_ref(pool, eval),
+            Self::Call(call) => call.eval_ref(pool, eval),
+            Self::Index(index) => index.eval_ref(pool, eval),
+            Self::Member(member) => member.eval_ref(pool, eval),
+            Self::Unwrap(unwrap) => unwrap.eval_ref(pool, eval),
+            Self::Is(is) => is.eval_ref(pool, eval),
+            Self::Cast(cast) => cast.eval_ref(pool, eval),
+            Self::Construct(construct) => construct.eval_ref(pool, eval),
+            Self::Scalar(scalar) => scalar.eval_ref(pool, eval),
+        }
+    }
+}
+
 impl ParseNode for ExprNode {
     fn parse_node(
         pool: &mut NodePool,
         src: Arc<Src>,
         tokenizer: &mut TokenIterator
     ) -> Result<NodeID, FatalParseError> {
-        let mut sides: Box<dyn ParseNodeFn> = Box::from(Self::parse_unop);
+        let mut sides: Box<dyn ParseNodeFn> = Box::from(Self::parse_type_ops);
         for prec in Prec::order() {
             sides = Box::from(
                 move |pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator|
@@ -163,7 +347,7 @@ impl ParseNode for ExprNode {
         sides(pool, src, tokenizer)
     }
     fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
-        ScalarExpr::peek(pos, tokenizer)
+        ScalarExpr::peek(pos, tokenizer) || op::Unary::peek(pos, tokenizer)
     }
 }
 
@@ -179,10 +363,39 @@ impl ResolveNode for ExprListNode {
         let _handle = checker.enter_scope(&mut self.scope);
         let tys = try_resolve_list!(&self.exprs, (pool, checker), (e, c) => e => (e, c));
         if let Some((e, c)) = tys.into_iter().last() {
-            if !c.get(pool).has_semicolon() {
+            // A block that ends in something like `return x;` never finishes
+            if !c.get(pool).has_semicolon() || e.is_never() {
                 return Some(e);
             }
         }
         Some(Ty::Void)
     }
 }
+
+impl EvalNode for ExprListNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        eval.with_scope(|eval| {
+            let mut last = Value::Void;
+            for (expr, semicolon) in &self.exprs {
+                let value = expr.eval_ref(pool, eval)?;
+                last = if semicolon.get(pool).has_semicolon() { Value::Void } else { value };
+            }
+            Ok(last)
+        })
+    }
+}
+
+/// The contents of a source file. Unlike a block, a module doesn't get a 
+/// scope of its own: its items are declared in the root scope under the 
+/// module's namespace, so that other files can refer to them
+#[derive(Debug, ParseNode)]
+pub struct ModuleNode {
+    exprs: Vec<(Expr, TerminatingSemicolon)>,
+}
+
+impl ResolveNode for ModuleNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        try_resolve_list!(&self.exprs, (pool, checker), (e, _) => e => e);
+        Some(Ty::Void)
+    }
+}
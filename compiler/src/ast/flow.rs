@@ -1,36 +1,317 @@
 
-use dash_macros::{ParseNode, ResolveNode};
+use dash_macros::{ParseNode, ResolveNode, EvalNode};
 use crate::{
-    parser::parse::{Separated, SeparatedWithTrailing, Node, NodePool},
-    checker::{resolve::{ResolveNode, ResolveRef}, ty::Ty, coherency::Checker}, try_resolve_ref
+    parser::parse::{Separated, SeparatedWithTrailing, NoConstructs, Node, NodePool},
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, ty::Ty, coherency::{Checker, ScopeID, ScopeKind},
+        entity::Entity, path
+    },
+    shared::{src::ArcSpan, logger::{Message, Level, Note, LoggerRef}},
+    try_resolve_ref,
+    eval::{EvalNode, EvalRef, EvalResult, Evaluator, Interrupt, value::Value}
 };
-use super::{token::{kw, delim, punct}, expr::{Expr, ExprList, IdentComponent}};
+use super::{token::{kw, delim, punct, op, Ident}, expr::{Expr, ExprList, IdentComponent}, ops::narrowings};
+
+/// The condition of an `if`, which is either a boolean or `let name = value` 
+/// to run the branch only if an optional isn't `none`
+#[derive(Debug, ParseNode)]
+#[parse(expected = "condition")]
+pub enum IfConditionNode {
+    Let(kw::Let, Ident, op::Seq, NoConstructs<Expr>),
+    Expr(NoConstructs<Expr>),
+}
+
+impl IfConditionNode {
+    /// The entities declared inside the branch run when this condition is 
+    /// true
+    fn bindings(&self, pool: &NodePool) -> Vec<(path::IdentPath, Ty, ArcSpan)> {
+        match self {
+            Self::Let(_, name, _, value) => {
+                let ty = match value.resolved_ty(pool).as_ref().map(Ty::reduce) {
+                    Some(Ty::Option { ty }) => ty.as_ref().clone(),
+                    _ => Ty::Invalid,
+                };
+                vec![(
+                    path::IdentPath::new([path::Ident::from(name.get(pool).to_string())], false),
+                    ty,
+                    name.get(pool).span_or_builtin(pool)
+                )]
+            }
+            Self::Expr(cond) => narrowings(**cond, pool),
+        }
+    }
+}
+
+impl ResolveNode for IfConditionNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        match self {
+            Self::Let(_, _, _, value) => {
+                let ty = value.try_resolve_ref(pool, checker)?;
+                if !ty.is_unreal() && !matches!(ty.reduce(), Ty::Option { ty: _ }) {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Cannot use 'if let' on a value of type {ty}"),
+                        value.get(pool).span_or_builtin(pool).as_ref()
+                    ).note(Note::new("'if let' can only unwrap optional values", false)));
+                }
+            }
+            Self::Expr(cond) => {
+                let ty = cond.try_resolve_ref(pool, checker)?;
+                checker.expect_ty_eq(Ty::Bool, ty, cond.get(pool).span(pool));
+            }
+        }
+        Some(Ty::Void)
+    }
+}
 
 #[derive(Debug, ParseNode)]
 pub struct IfNode {
     if_kw: kw::If,
-    cond: Expr,
+    cond: IfCondition,
     truthy: delim::Braced<ExprList>,
     falsy: Option<(kw::Else, Else)>,
+    /// The scope of the truthy branch, which holds the `if let` binding and 
+    /// narrowed variables
+    #[parse(skip)]
+    scope: Option<ScopeID>,
 }
 
 impl ResolveNode for IfNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        let cond = self.cond.try_resolve_ref(pool, checker)?;
-        let truthy = self.truthy.try_resolve_ref(pool, checker)?;
+        self.cond.try_resolve_ref(pool, checker)?;
+        let new_scope = self.scope.is_none();
+        let truthy = {
+            let _scope = checker.enter_scope(&mut self.scope);
+            // Narrowed variables shadow the original ones inside the branch
+            if new_scope {
+                for (name, ty, span) in self.cond.get(pool).bindings(pool) {
+                    let _ = checker.scope().entities_mut().try_push(&name, Entity::new(ty, span, true));
+                }
+            }
+            self.truthy.try_resolve_ref(pool, checker)?
+        };
+        // Without an `else`, the `if` finishes even if its branch never does
+        if self.falsy.is_none() && truthy.is_never() {
+            return Some(Ty::Void);
+        }
         let falsy = try_resolve_ref!(self.falsy, (pool, checker), Some((_, e)) => e);
-        checker.expect_ty_eq(cond, Ty::Bool, self.cond.get(pool).span(pool));
         checker.expect_ty_eq(truthy, falsy, self.span(pool)).into()
     }
 }
 
-#[derive(Debug, ParseNode, ResolveNode)]
+impl EvalNode for IfNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        // The value bound by `if let`, if the branch should be run
+        let binding = match &*self.cond.get(pool) {
+            IfConditionNode::Let(_, name, _, value) => match value.eval_ref(pool, eval)? {
+                Value::None => None,
+                value => Some(Some((name.get(pool).to_string(), value))),
+            },
+            IfConditionNode::Expr(cond) => match cond.eval_ref(pool, eval)? {
+                Value::Bool(true) => Some(None),
+                Value::Bool(false) => None,
+                other => return Err(Interrupt::Error(
+                    format!("Expected a bool, got {}", other.kind()),
+                    cond.get(pool).span_or_builtin(pool)
+                )),
+            },
+        };
+        match binding {
+            Some(binding) => eval.with_scope(|eval| {
+                if let Some((name, value)) = binding {
+                    eval.declare(name, value);
+                }
+                self.truthy.eval_ref(pool, eval)
+            }),
+            None => match self.falsy {
+                Some((_, falsy)) => falsy.eval_ref(pool, eval),
+                None => Ok(Value::Void),
+            },
+        }
+    }
+}
+
+#[derive(Debug, ParseNode, ResolveNode, EvalNode)]
 #[parse(expected = "block or if statement")]
 pub enum ElseNode {
     Else(delim::Braced<ExprList>),
     ElseIf(If),
 }
 
+#[derive(Debug, ParseNode)]
+pub struct ForNode {
+    for_kw: kw::For,
+    binding: Ident,
+    in_kw: kw::In,
+    iterable: NoConstructs<Expr>,
+    body: delim::Braced<ExprList>,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for ForNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let iterable = self.iterable.try_resolve_ref(pool, checker)?;
+        let span = self.iterable.get(pool).span_or_builtin(pool);
+        let item = if checker.expect_ty_decided(iterable.clone(), Some(span.clone())) {
+            checker.iter_item_ty(&iterable)
+        }
+        else {
+            Some(Ty::Invalid)
+        };
+
+        let new_scope = self.scope.is_none();
+        let _scope = checker.enter_scope_of_kind(&mut self.scope, ScopeKind::Loop);
+        if new_scope {
+            let _ = checker.scope().entities_mut().try_push(
+                &path::IdentPath::new([path::Ident::from(self.binding.get(pool).to_string())], false),
+                Entity::new(
+                    item.clone().unwrap_or(Ty::Invalid),
+                    self.binding.get(pool).span_or_builtin(pool),
+                    true
+                )
+            );
+        }
+        self.body.try_resolve_ref(pool, checker)?;
+
+        if item.is_none() {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot iterate over a value of type {iterable}"),
+                span.as_ref()
+            ).note(Note::new(
+                "Types can be made iterable by adding a 'next(this) -> Item?' \
+                method, or an 'iter(this)' method that returns an iterator",
+                true
+            )));
+        }
+        Some(Ty::Void)
+    }
+}
+
+/// Run the body of a loop once, returning whether the loop should go on
+fn eval_loop_body(body: &delim::Braced<ExprList>, pool: &NodePool, eval: &mut Evaluator) -> Result<bool, Interrupt> {
+    match body.eval_ref(pool, eval) {
+        Ok(_) | Err(Interrupt::Continue) => Ok(true),
+        Err(Interrupt::Break) => Ok(false),
+        Err(other) => Err(other),
+    }
+}
+
+impl EvalNode for ForNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let items = match self.iterable.eval_ref(pool, eval)? {
+            Value::String(s) => s.chars().map(|c| Value::String(c.to_string())).collect::<Vec<_>>(),
+            Value::List(items) => items,
+            Value::Map(entries) => entries.into_iter()
+                .map(|(key, value)| Value::Struct {
+                    name: String::from("MapEntry"),
+                    fields: vec![(String::from("key"), key), (String::from("value"), value)],
+                })
+                .collect(),
+            other => return Err(Interrupt::Error(
+                format!("Iterating over {} can not be done at compile time", other.kind()),
+                self.iterable.get(pool).span_or_builtin(pool)
+            )),
+        };
+        let binding = self.binding.get(pool).to_string();
+        for item in items {
+            let go_on = eval.with_scope(|eval| {
+                eval.declare(binding.clone(), item);
+                eval_loop_body(&self.body, pool, eval)
+            })?;
+            if !go_on {
+                break;
+            }
+        }
+        Ok(Value::Void)
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct WhileNode {
+    while_kw: kw::While,
+    cond: NoConstructs<Expr>,
+    body: delim::Braced<ExprList>,
+    #[parse(skip)]
+    scope: Option<ScopeID>,
+}
+
+impl ResolveNode for WhileNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let cond = self.cond.try_resolve_ref(pool, checker)?;
+        let _scope = checker.enter_scope_of_kind(&mut self.scope, ScopeKind::Loop);
+        self.body.try_resolve_ref(pool, checker)?;
+        checker.expect_ty_eq(Ty::Bool, cond, self.cond.get(pool).span(pool));
+        Some(Ty::Void)
+    }
+}
+
+impl EvalNode for WhileNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        loop {
+            match self.cond.eval_ref(pool, eval)? {
+                Value::Bool(true) => {}
+                Value::Bool(false) => break,
+                other => return Err(Interrupt::Error(
+                    format!("Expected a bool, got {}", other.kind()),
+                    self.cond.get(pool).span_or_builtin(pool)
+                )),
+            }
+            if !eval_loop_body(&self.body, pool, eval)? {
+                break;
+            }
+        }
+        Ok(Value::Void)
+    }
+}
+
+fn expect_in_loop(kw: &str, span: ArcSpan, checker: &Checker) {
+    if !checker.in_loop() {
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            format!("'{kw}' can only be used inside a loop"),
+            span.as_ref()
+        ));
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct BreakNode {
+    break_kw: kw::Break,
+}
+
+impl ResolveNode for BreakNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        expect_in_loop("break", self.span_or_builtin(pool), checker);
+        Some(Ty::Never)
+    }
+}
+
+impl EvalNode for BreakNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Err(Interrupt::Break)
+    }
+}
+
+#[derive(Debug, ParseNode)]
+pub struct ContinueNode {
+    continue_kw: kw::Continue,
+}
+
+impl ResolveNode for ContinueNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        expect_in_loop("continue", self.span_or_builtin(pool), checker);
+        Some(Ty::Never)
+    }
+}
+
+impl EvalNode for ContinueNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Err(Interrupt::Continue)
+    }
+}
+
 #[derive(Debug, ParseNode)]
 pub struct ReturnNode {
     return_kw: kw::Return,
@@ -39,11 +320,48 @@ pub struct ReturnNode {
 
 impl ResolveNode for ReturnNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        let expr = try_resolve_ref!(self.expr, (pool, checker), Some(e) => e);
+        let span = self.span_or_builtin(pool);
+        let Some(ret_ty) = checker.return_ty() else {
+            try_resolve_ref!(self.expr, (pool, checker), Some(e) => e);
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                "'return' can only be used inside a function",
+                span.as_ref()
+            ));
+            return Some(Ty::Never);
+        };
+        let value = checker.with_expected_ty(ret_ty.clone(), |checker| {
+            Some(try_resolve_ref!(self.expr, (pool, checker), Some(e) => e else Ty::Void))
+        })?;
+        match ret_ty {
+            // Functions without a declared return type return whatever their 
+            // `return`s do
+            None => checker.push_return(value, self.expr.and_then(|e| e.get(pool).span(pool)).unwrap_or(span)),
+            Some(ret_ty) if self.expr.is_none() && !ret_ty.is_unreal() && *ret_ty.reduce() != Ty::Void => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Expected a value of type {ret_ty} to be returned"),
+                    span.as_ref()
+                ));
+            }
+            Some(ret_ty) => {
+                checker.expect_ty_eq(ret_ty, value, self.expr.and_then(|e| e.get(pool).span(pool)));
+            }
+        }
         Some(Ty::Never)
     }
 }
 
+impl EvalNode for ReturnNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let value = match self.expr {
+            Some(expr) => expr.eval_ref(pool, eval)?,
+            None => Value::Void,
+        };
+        Err(Interrupt::Return(value))
+    }
+}
+
 #[derive(Debug, ParseNode)]
 #[parse(expected = "identifier")]
 enum UsingComponentNode {
@@ -51,6 +369,22 @@ enum UsingComponentNode {
     Single(IdentComponent),
 }
 
+impl UsingComponentNode {
+    /// Get the paths imported by this component, each starting with `prefix`
+    fn expand(&self, prefix: Vec<path::Ident>, pool: &NodePool) -> Vec<(Vec<path::Ident>, ArcSpan)> {
+        match self {
+            Self::Single(i) => {
+                let mut path = prefix;
+                path.push(i.get(pool).to_ident(pool));
+                vec![(path, i.get(pool).span_or_builtin(pool))]
+            }
+            Self::Multi(items) => items.get(pool).value.iter()
+                .flat_map(|c| c.get(pool).expand(prefix.clone(), pool))
+                .collect(),
+        }
+    }
+}
+
 impl ResolveNode for UsingComponentNode {
     fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
         Some(Ty::Invalid)
@@ -63,6 +397,22 @@ struct UsingPathNode {
     path: Separated<UsingComponent, punct::Namespace>,
 }
 
+impl UsingPathNode {
+    /// Get every full path imported, with `A::{b, c}` expanded to `A::b` and 
+    /// `A::c`
+    fn paths(&self, pool: &NodePool) -> Vec<(path::IdentPath, ArcSpan)> {
+        let mut paths = vec![(vec![], ArcSpan::builtin())];
+        for comp in self.path.iter() {
+            paths = paths.into_iter()
+                .flat_map(|(prefix, _)| comp.get(pool).expand(prefix, pool))
+                .collect();
+        }
+        paths.into_iter()
+            .map(|(p, span)| (path::IdentPath::new(p, self.absolute.is_some()), span))
+            .collect()
+    }
+}
+
 impl ResolveNode for UsingPathNode {
     fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
         Some(Ty::Invalid)
@@ -73,18 +423,171 @@ impl ResolveNode for UsingPathNode {
 pub struct UsingNode {
     using_kw: kw::Using,
     path: UsingPath,
+    /// Paths that have already been imported on previous passes
+    #[parse(skip)]
+    imported: Vec<path::IdentPath>,
+    /// Paths that couldn't be found on the latest pass
+    #[parse(skip)]
+    unresolved: Vec<(path::IdentPath, ArcSpan)>,
+}
+
+/// Report an import clashing with an existing item in the current scope. 
+/// Importing an item into the scope it's declared in is allowed and does 
+/// nothing
+fn report_import_conflict(
+    name: &path::IdentPath, old: Option<ArcSpan>, decl: ArcSpan, span: &ArcSpan, checker: &Checker
+) {
+    if let Some(old) = old.filter(|old| *old != decl) {
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            format!("Cannot import {name} because it has already been defined in this scope"),
+            span.as_ref()
+        ).note(Note::new_at("Previous definition here", old.as_ref())));
+    }
 }
 
 impl ResolveNode for UsingNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        todo!()
+        self.unresolved.clear();
+        for (path, span) in self.path.get(pool).paths(pool) {
+            if self.imported.contains(&path) {
+                continue;
+            }
+            let Some((_, last)) = path.split_last() else { continue };
+            let name = path::IdentPath::new([last.clone()], false);
+            let ty = checker.scopes().find_map(|s| s.types().find(&path).cloned());
+            let ent = checker.scopes().find_map(|s| s.entities().find(&path).cloned());
+            if ty.is_none() && ent.is_none() {
+                self.unresolved.push((path, span));
+                continue;
+            }
+            if let Some(ty) = ty {
+                let old = checker.scope().types_mut().try_push(&name, ty.clone()).err().map(|old| old.span());
+                report_import_conflict(&name, old, ty.span(), &span, checker);
+            }
+            if let Some(ent) = ent {
+                let old = checker.scope().entities_mut().try_push(&name, ent.clone()).err().map(|old| old.span());
+                report_import_conflict(&name, old, ent.span(), &span, checker);
+            }
+            self.imported.push(path);
+        }
+        self.unresolved.is_empty().then_some(Ty::Void)
+    }
+    fn log_unresolved_reason(&self, _pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        for (path, span) in &self.unresolved {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot find {path} to import"),
+                span.as_ref()
+            ));
+        }
+    }
+}
+
+impl EvalNode for UsingNode {
+    fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+        Ok(Value::Void)
     }
 }
 
-#[derive(Debug, ParseNode, ResolveNode)]
+#[derive(Debug, ParseNode, ResolveNode, EvalNode)]
 #[parse(expected = "control flow expression")]
 pub enum FlowNode {
     If(If),
+    For(For),
+    While(While),
+    Break(Break),
+    Continue(Continue),
     Return(Return),
     Using(Using),
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::check_errors;
+
+    #[test]
+    fn break_outside_loop() {
+        assert_eq!(
+            check_errors("fun test() -> void {\n    break;\n}\n"),
+            ["'break' can only be used inside a loop"]
+        );
+    }
+
+    #[test]
+    fn continue_outside_loop() {
+        assert_eq!(
+            check_errors("fun test() -> void {\n    continue;\n}\n"),
+            ["'continue' can only be used inside a loop"]
+        );
+    }
+
+    #[test]
+    fn break_inside_loop() {
+        assert_eq!(
+            check_errors("fun test() -> void {\n    while true {\n        break;\n    }\n}\n"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn narrowing() {
+        assert_eq!(
+            check_errors(
+                "fun test(a: int?, b: int?) -> int {\n    if let c = a {\n        return c + 1;\n    }\n    \
+                if b is int {\n        return b * 2;\n    }\n    0\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors("fun test(a: int?) -> int {\n    if a is int {}\n    a + 1\n}\n"),
+            ["Cannot use operator '+' on types int? and int"]
+        );
+    }
+
+    #[test]
+    fn invalid_type_tests() {
+        assert_eq!(
+            check_errors(
+                "fun test(a: int) -> int {\n    if let c = a {\n        return c;\n    }\n    \
+                if a is string {}\n    0\n}\n"
+            ),
+            ["Cannot use 'if let' on a value of type int", "A value of type int can never be of type string"]
+        );
+    }
+    #[test]
+    fn empty_return_from_int_function() {
+        assert_eq!(
+            check_errors("fun test() -> int {\n    return;\n}\n"),
+            ["Expected a value of type int to be returned"]
+        );
+    }
+
+    #[test]
+    fn value_return_from_int_function() {
+        assert_eq!(check_errors("fun test() -> int {\n    return 5;\n}\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn returns_and_recursion() {
+        assert_eq!(
+            check_errors(
+                "fun fact(n: int) -> int {\n    if n < 2 {\n        return 1;\n    }\n    n * fact(n - 1)\n}\n\
+                fun sign(n: int) {\n    if n < 0 {\n        return -1;\n    }\n    return 1;\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(
+                "fun fact(n: int) {\n    n * fact(n - 1)\n}\n\
+                fun name(n: int) {\n    if n < 0 {\n        return 1;\n    }\n    return \"a\";\n}\n\
+                fun test() -> string {\n    return 5;\n}\n"
+            ),
+            [
+                "Cannot convert from type string to int",
+                "Cannot convert from type int to string",
+                "Recursive function fact needs an explicit return type",
+            ]
+        );
+    }
+}
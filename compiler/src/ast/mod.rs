@@ -6,6 +6,7 @@ pub mod expr;
 pub mod ops;
 pub mod atom;
 pub mod flow;
+pub mod codegen;
 
 #[macro_export]
 macro_rules! try_resolve_ref {
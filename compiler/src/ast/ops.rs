@@ -1,12 +1,24 @@
 
 use std::{sync::Arc, collections::HashMap};
-use dash_macros::ParseNode;
+use dash_macros::{ParseNode, ResolveNode};
 use crate::{
     parser::{parse::{FatalParseError, ParseNodeFn, SeparatedWithTrailing, NodePool, RefToNode, Node, ParseRef, NodeID}, tokenizer::TokenIterator},
     shared::{src::{Src, ArcSpan}, logger::{Message, Level, Note, LoggerRef}},
-    checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty, path}, ice
+    checker::{
+        resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::{Ty, Field}, entity::Entity, path,
+        operators::{self, BinOpResolution}
+    },
+    ice,
+    eval::{EvalNode, EvalRef, EvalResult, Evaluator, Interrupt, value::{Value, Function}, list}
+};
+use super::{
+    expr::{Expr, ExprNode, ScalarExprNode},
+    atom::{AtomNode, ItemUseNode},
+    token::{op, delim, kw, Ident, punct},
+    ty::TypeExpr,
+    codegen::Splice,
+    decl::StructField
 };
-use super::{expr::Expr, token::{op, delim, Ident, punct}};
 
 #[derive(Debug, ParseNode)]
 #[parse(expected = "expression or named argument")]
@@ -24,7 +36,7 @@ impl ResolveNode for ArgNode {
 #[derive(Debug)]
 pub struct CallNode {
     target: Expr,
-    args: delim::Parenthesized<SeparatedWithTrailing<Arg, punct::Comma>>,
+    args: delim::Parenthesized<Option<SeparatedWithTrailing<Arg, punct::Comma>>>,
 }
 pub type Call = RefToNode<CallNode>;
 
@@ -41,6 +53,25 @@ impl CallNode {
         };
         Ok(pool.add(res))
     }
+    /// The parameters that may be left out when calling the target, if it's 
+    /// a function declared with default values for them
+    fn optional_params(&self, pool: &NodePool) -> Vec<String> {
+        let entity = match &*self.target.get(pool) {
+            ExprNode::Member(member) => member.get(pool).method.clone(),
+            ExprNode::Scalar(scalar) => match *scalar.get(pool) {
+                ScalarExprNode::Atom(atom) => match *atom.get(pool) {
+                    AtomNode::ItemUse(item) => match &*item.get(pool) {
+                        ItemUseNode::Ident(_, _, entity) => entity.clone(),
+                        ItemUseNode::This(_) => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        entity.map(|e| e.optional_params().to_vec()).unwrap_or_default()
+    }
 }
 
 impl Node for CallNode {
@@ -49,122 +80,852 @@ impl Node for CallNode {
     }
 }
 
+impl CallNode {
+    /// Whether this calls a method through optional chaining, like 
+    /// `parent?.child()`, in which case the call is skipped if the target 
+    /// is `none`
+    fn is_optional_chain(&self, pool: &NodePool) -> bool {
+        matches!(*self.target.get(pool), ExprNode::Member(m) if m.get(pool).is_optional())
+    }
+    /// The list a method that modifies it, like `push`, is called on
+    fn modified_list(&self, pool: &NodePool) -> Option<Expr> {
+        let ExprNode::Member(member) = *self.target.get(pool) else {
+            return None;
+        };
+        let member = member.get(pool);
+        member.method.as_ref()?.intrinsic().filter(|i| list::modifies_list(i))?;
+        Some(member.target)
+    }
+}
+
 impl ResolveNode for CallNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        let target = self.target.try_resolve_ref(pool, checker)?;
-        let args = self.args.get(pool).value.iter()
-            .map(|arg| match *arg.get(pool) {
-                ArgNode::Unnamed(value) => {
-                    (None, value.try_resolve_ref(pool, checker), value.get(pool).span(pool))
+        let target = checker.with_expected_ty(None, |c| self.target.try_resolve_ref(pool, c))?;
+        if self.is_optional_chain(pool) {
+            if let Ty::Option { ty } = target.reduce() {
+                let param_tys = match ty.reduce() {
+                    Ty::Function { params, ret_ty: _ } => params.clone(),
+                    _ => Vec::new(),
+                };
+                let args = resolve_args(&self.args, &param_tys, pool, checker)?;
+                if let Some(list) = self.modified_list(pool) {
+                    check_assignable(list, pool, checker);
                 }
-                ArgNode::Named(name, _, value) => {
-                    (Some(name.get(pool).to_string()), value.try_resolve_ref(pool, checker), value.get(pool).span(pool))
+                return Some(match ty.reduce() {
+                    Ty::Function { params, ret_ty } => {
+                        check_args(params, &args, &self.optional_params(pool), self.span(pool), checker);
+                        ret_ty.as_ref().clone().into_optional()
+                    }
+                    _ => Ty::Invalid,
+                });
+            }
+        }
+        // Arguments are expected to be of the type of their parameter
+        let (generics, fun) = match target.reduce() {
+            Ty::Generic { params, ty } => (params.clone(), ty.as_ref().clone()),
+            other => (Vec::new(), other.clone()),
+        };
+        let param_tys = match fun {
+            Ty::Function { ref params, ret_ty: _ } => params.clone(),
+            _ => Vec::new(),
+        };
+        // The type parameters of generic functions are inferred from the 
+        // arguments, and then from the type the result is expected to have
+        let mut inferred = Vec::new();
+        let args = resolve_args_inferring(&self.args, &param_tys, &generics, &mut inferred, pool, checker)?;
+        let target = match fun {
+            Ty::Function { params: _, ref ret_ty } if !generics.is_empty() => {
+                if let Some(expected) = checker.expected_ty() {
+                    ret_ty.infer(&expected, &generics, &mut inferred);
+                }
+                let fun = Ty::Generic { params: generics, ty: fun.clone().into() };
+                match checker.instantiate_inferred(&fun, &inferred, self.span(pool)) {
+                    Ty::Invalid => return Some(Ty::Invalid),
+                    instance => instance,
                 }
-            })
-            .map(|(a, e, s)| e.map(|e| (a, e, s)))
-            .collect::<Option<Vec<_>>>()?;
+            }
+            _ => target,
+        };
+        // Lists modified by a method like `push` must be assignable
+        if let Some(list) = self.modified_list(pool) {
+            check_assignable(list, pool, checker);
+        }
         match target {
             Ty::Function { params, ret_ty } => {
-                let mut arg_ix = 0usize;
-                let mut encountered_named = None;
-                let mut passed: HashMap<String, ArcSpan> = HashMap::new();
-                for (name, ty, span) in &args {
-                    if let Some(name) = name {
-                        encountered_named = Some(span.clone());
-                        match passed.get(name) {
-                            Some(old) => {
-                                checker.logger().lock().unwrap().log(Message::new(
-                                    Level::Error,
-                                    format!("Parameter '{name}' has already been passed"),
-                                    span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                ).note(Note::new_at(
-                                    "Previous passing here",
-                                    old.as_ref()
-                                )));
-                            }
-                            None => {
-                                match params.iter().find(|p| p.0.as_ref() == Some(name)) {
-                                    Some((_, pty)) => {
-                                        checker.expect_ty_eq(ty.clone(), pty.clone(), span.clone());
-                                    }
-                                    None => {
-                                        checker.logger().lock().unwrap().log(Message::new(
-                                            Level::Error,
-                                            format!("Unknown parameter '{name}'"),
-                                            span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                        ));
-                                    }
-                                }
-                                passed.insert(name.clone(), span.clone().unwrap_or(ArcSpan::builtin()));
-                            }
+                check_args(&params, &args, &self.optional_params(pool), self.span(pool), checker);
+                Some(ret_ty.as_ref().clone())
+            }
+            // Whatever made the target invalid has already been reported
+            other if other.is_unreal() => Some(Ty::Invalid),
+            other => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot call an expression of type {other}"),
+                    self.span(pool).unwrap_or_default().as_ref()
+                ));
+                Some(Ty::Invalid)
+            }
+        }
+    }
+}
+
+impl EvalNode for CallNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let target = self.target.eval_ref(pool, eval)?;
+        if target == Value::None && self.is_optional_chain(pool) {
+            return Ok(Value::None);
+        }
+        let mut args = eval_args(&self.args, pool, eval)?;
+        let span = self.span_or_builtin(pool);
+        // The modified list is stored back where it came from
+        if let (Some(list), Value::Function(Function::Bound { fun, this })) = (self.modified_list(pool), &target) {
+            if let Function::Intrinsic(name) = **fun {
+                args.insert(0, (None, this.as_ref().clone()));
+                let (res, modified) = list::call_list_method(name, pool, args, span.clone(), eval)?;
+                eval_assign(list, modified, pool, eval, span)?;
+                return Ok(res);
+            }
+        }
+        match target {
+            Value::Function(fun) => eval.call(pool, fun, args, span),
+            other => Err(Interrupt::Error(format!("Cannot call a value of type {}", other.kind()), span)),
+        }
+    }
+}
+
+pub(crate) type ArgList = delim::Parenthesized<Option<SeparatedWithTrailing<Arg, punct::Comma>>>;
+
+/// A resolved argument: its name if it was passed by name, its type and its 
+/// span
+pub(crate) type ResolvedArg = (Option<String>, Ty, Option<ArcSpan>);
+
+/// Resolve a list of arguments, using the types of the parameters they are 
+/// passed to as the expected types
+pub(crate) fn resolve_args(
+    args: &ArgList,
+    param_tys: &[(Option<String>, Ty)],
+    pool: &NodePool,
+    checker: &mut Checker
+) -> Option<Vec<ResolvedArg>> {
+    resolve_args_inferring(args, param_tys, &[], &mut Vec::new(), pool, checker)
+}
+
+/// Resolve a list of arguments passed to parameters whose types may contain 
+/// the type parameters `generics`, inferring their types from the arguments 
+/// in order. Later arguments are expected to be of the parameter types with 
+/// what has been inferred so far, so that lambdas can infer their parameters
+pub(crate) fn resolve_args_inferring(
+    args: &ArgList,
+    param_tys: &[(Option<String>, Ty)],
+    generics: &[Ty],
+    inferred: &mut Vec<(Ty, Ty)>,
+    pool: &NodePool,
+    checker: &mut Checker
+) -> Option<Vec<ResolvedArg>> {
+    let mut positional_ix = 0usize;
+    let mut res = Vec::new();
+    for arg in args.get(pool).value.iter().flat_map(|a| a.iter()) {
+        let (name, value) = match *arg.get(pool) {
+            ArgNode::Unnamed(value) => (None, value),
+            ArgNode::Named(name, _, value) => (Some(name.get(pool).to_string()), value),
+        };
+        let param = match name {
+            Some(ref name) => param_tys.iter().find(|p| p.0.as_ref() == Some(name)),
+            None => {
+                positional_ix += 1;
+                param_tys.get(positional_ix - 1)
+            }
+        };
+        // Type parameters that haven't been inferred yet could be anything
+        let unknown = inferred.iter().cloned()
+            .chain(generics.iter().map(|g| (g.clone(), Ty::Invalid)))
+            .collect::<Vec<_>>();
+        let param = param.map(|p| p.1.substitute(inferred));
+        let expected = param.as_ref().map(|p| p.substitute(&unknown));
+        let ty = checker.with_expected_ty(expected, |checker| value.try_resolve_ref(pool, checker))?;
+        if let Some(param) = param {
+            param.infer(&ty, generics, inferred);
+        }
+        res.push((name, ty, value.get(pool).span(pool)));
+    }
+    Some(res)
+}
+
+/// Evaluate a list of arguments, keeping the names of named arguments
+pub(crate) fn eval_args(
+    args: &ArgList,
+    pool: &NodePool,
+    eval: &mut Evaluator
+) -> Result<Vec<(Option<String>, Value)>, Interrupt> {
+    args.get(pool).value.iter()
+        .flat_map(|a| a.iter())
+        .map(|arg| match *arg.get(pool) {
+            ArgNode::Unnamed(value) => Ok((None, value.eval_ref(pool, eval)?)),
+            ArgNode::Named(name, _, value) => Ok((Some(name.get(pool).to_string()), value.eval_ref(pool, eval)?)),
+        })
+        .collect()
+}
+
+/// Check that resolved arguments match the parameters of a function. The 
+/// parameters named in `optional` have default values, so they may be left out
+pub(crate) fn check_args(
+    params: &[(Option<String>, Ty)],
+    args: &[ResolvedArg],
+    optional: &[String],
+    call_span: Option<ArcSpan>,
+    checker: &mut Checker
+) {
+    let mut arg_ix = 0usize;
+    let mut encountered_named = None;
+    let mut passed: HashMap<String, ArcSpan> = HashMap::new();
+    for (name, ty, span) in args {
+        if let Some(name) = name {
+            encountered_named = Some(span.clone());
+            match passed.get(name) {
+                Some(old) => {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Parameter '{name}' has already been passed"),
+                        span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
+                    ).note(Note::new_at(
+                        "Previous passing here",
+                        old.as_ref()
+                    )));
+                }
+                None => {
+                    match params.iter().find(|p| p.0.as_ref() == Some(name)) {
+                        Some((_, pty)) => {
+                            checker.expect_ty_eq(pty.clone(), ty.clone(), span.clone());
                         }
-                    }
-                    else {
-                        match encountered_named.clone() {
-                            Some(e_span) => {
-                                checker.logger().lock().unwrap().log(Message::new(
-                                    Level::Error,
-                                    "Cannot pass positional arguments after named arguments \
-                                    have been passed",
-                                    span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                ).note(Note::hint(
-                                    "Move this named argument to the end of the arguments pool",
-                                    e_span.unwrap_or(ArcSpan::builtin()).as_ref()
-                                )));
-                            }
-                            None => {
-                                match params.get(arg_ix) {
-                                    Some((name, pty)) => {
-                                        if let Some(name) = name {
-                                            passed.insert(name.clone(), span.clone().unwrap_or(ArcSpan::builtin()));
-                                        }
-                                        checker.expect_ty_eq(ty.clone(), pty.clone(), span.clone());
-                                    }
-                                    None => {
-                                        checker.logger().lock().unwrap().log(Message::new(
-                                            Level::Error,
-                                            "Too many positional arguments",
-                                            span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
-                                        ).note(Note::new(format!(
-                                            "Function has only {} parameters, but {} were passed",
-                                            params.len(), args.len()
-                                        ), false)));
-                                    }
-                                }
-                            }
+                        None => {
+                            checker.logger().lock().unwrap().log(Message::new(
+                                Level::Error,
+                                format!("Unknown parameter '{name}'"),
+                                span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
+                            ));
                         }
                     }
-                    arg_ix += 1;
+                    passed.insert(name.clone(), span.clone().unwrap_or(ArcSpan::builtin()));
                 }
-                if arg_ix < params.len() {
+            }
+        }
+        else {
+            match encountered_named.clone() {
+                Some(e_span) => {
                     checker.logger().lock().unwrap().log(Message::new(
                         Level::Error,
-                        "Missing arguments",
-                        self.span(pool).unwrap_or(ArcSpan::builtin()).as_ref()
-                    ).note(Note::new(format!(
-                        "Function has {} parameters, but only {} were passed",
-                        params.len(), args.len()
-                    ), false)));
+                        "Cannot pass positional arguments after named arguments \
+                        have been passed",
+                        span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
+                    ).note(Note::hint(
+                        "Move this named argument to the end of the arguments pool",
+                        e_span.unwrap_or(ArcSpan::builtin()).as_ref()
+                    )));
+                }
+                None => {
+                    match params.get(arg_ix) {
+                        Some((name, pty)) => {
+                            if let Some(name) = name {
+                                passed.insert(name.clone(), span.clone().unwrap_or(ArcSpan::builtin()));
+                            }
+                            checker.expect_ty_eq(pty.clone(), ty.clone(), span.clone());
+                        }
+                        None => {
+                            checker.logger().lock().unwrap().log(Message::new(
+                                Level::Error,
+                                "Too many positional arguments",
+                                span.clone().unwrap_or(ArcSpan::builtin()).as_ref()
+                            ).note(Note::new(format!(
+                                "Function has only {} parameters, but {} were passed",
+                                params.len(), args.len()
+                            ), false)));
+                        }
+                    }
                 }
-                Some(ret_ty.as_ref().clone())
             }
-            other => {
+        }
+        arg_ix += 1;
+    }
+    // Parameters with default values may be left out
+    let missing = params.iter().enumerate().any(|(ix, (name, _))| match name {
+        Some(name) => !passed.contains_key(name) && !optional.contains(name),
+        None => ix >= arg_ix,
+    });
+    if missing {
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            "Missing arguments",
+            call_span.unwrap_or_default().as_ref()
+        ).note(Note::new(format!(
+            "Function has {} parameters, but only {} were passed",
+            params.len(), args.len()
+        ), false)));
+    }
+}
+
+/// The name of a member, which may be spliced into a codegen template like 
+/// `value.@(field.name)`
+#[derive(Debug, ParseNode, ResolveNode)]
+#[parse(expected = "member name")]
+pub enum MemberNameNode {
+    Splice(Splice),
+    Ident(Ident),
+}
+
+#[derive(Debug)]
+pub struct MemberNode {
+    target: Expr,
+    /// The `?` of optional chaining, like `parent?.child`
+    question: Option<op::Question>,
+    dot: punct::Dot,
+    name: MemberName,
+    /// The field this refers to, if it's not a method
+    field: Option<Field>,
+    /// The method this refers to, if it's not a field
+    method: Option<Entity>,
+}
+pub type Member = RefToNode<MemberNode>;
+
+impl MemberNode {
+    pub(crate) fn parse_with(
+        target: Expr,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let question = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let dot = ParseRef::parse_ref(pool, src.clone(), tokenizer)?;
+        let name: MemberName = ParseRef::parse_ref(pool, src, tokenizer)?;
+        if let MemberNameNode::Splice(splice) = *name.get(pool) {
+            splice.get_mut(pool).set_member_name();
+        }
+        Ok(pool.add(Self { target, question, dot, name, field: None, method: None }))
+    }
+}
+
+impl Node for MemberNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.target, &self.question, &self.dot, &self.name]
+    }
+}
+
+impl ResolveNode for MemberNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        if !checker.expect_ty_decided(target.clone(), self.target.get(pool).span(pool)) || target.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        // Optional chaining accesses the member of the value inside the 
+        // optional, and the result is optional too
+        let target = match (self.question, target.reduce()) {
+            (Some(_), Ty::Option { ty }) if ty.is_unreal() => return Some(Ty::Invalid),
+            (Some(_), Ty::Option { ty }) => ty.as_ref().clone(),
+            (Some(question), _) => {
+                let span = question.get(pool).span_or_builtin(pool);
                 checker.logger().lock().unwrap().log(Message::new(
                     Level::Error,
-                    format!("Cannot call an expression of type {other}"),
-                    self.span(pool).unwrap_or(ArcSpan::builtin()).as_ref()
-                ));
+                    format!("Cannot use '?.' on a value of type {target}"),
+                    span.as_ref()
+                ).note(Note::hint("Only optional values can be chained. Use '.' instead", span.as_ref())));
+                return Some(Ty::Invalid);
+            }
+            (None, _) => target,
+        };
+        let name = match *self.name.get(pool) {
+            MemberNameNode::Ident(name) => name.get(pool).to_string(),
+            MemberNameNode::Splice(splice) => {
+                splice.try_resolve_ref(pool, checker)?;
+                match splice.get(pool).fill_name() {
+                    Some(name) => name,
+                    None => return Some(Ty::Invalid),
+                }
+            }
+        };
+        if let Some(ty) = checker.find_member(&target, &name) {
+            self.field = checker.find_field(&target, &name);
+            self.method = checker.find_method(&target, &name);
+            return Some(if self.is_optional() { ty.into_optional() } else { ty });
+        }
+        let members = checker.members_of(&target);
+        let span = self.name.get(pool).span_or_builtin(pool);
+        let msg = Message::new(
+            Level::Error,
+            format!("Unknown member '{name}' on type {target}"),
+            span.as_ref()
+        );
+        if let Ty::Option { ty } = target.reduce() {
+            if checker.find_member(ty, &name).is_some() {
+                checker.logger().lock().unwrap().log(msg.note(Note::hint(
+                    format!("Type {target} is optional. Use '?.' to access members of optional values, or unwrap it with '!'"),
+                    span.as_ref()
+                )));
+                return Some(Ty::Invalid);
+            }
+        }
+        checker.logger().lock().unwrap().log(if members.is_empty() {
+            msg.note(Note::new(format!("Type {target} has no members"), false))
+        }
+        else {
+            msg.note(Note::new(format!("Available members are {}", members.join(", ")), false))
+        });
+        Some(Ty::Invalid)
+    }
+}
+
+impl EvalNode for MemberNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let target = self.target.eval_ref(pool, eval)?;
+        if target == Value::None && self.is_optional() {
+            return Ok(Value::None);
+        }
+        let span = self.name.get(pool).span_or_builtin(pool);
+        let name = match *self.name.get(pool) {
+            MemberNameNode::Ident(name) => name.get(pool).to_string(),
+            MemberNameNode::Splice(splice) => splice.get(pool).fill_name().ok_or_else(|| Interrupt::Error(
+                "'@(...)' can only be used inside codegen blocks".into(),
+                span.clone()
+            ))?,
+        };
+        // Computed properties are read through their getter
+        if let Some(property) = self.property(eval) {
+            return property.get(pool).eval_get(pool, eval, target, span);
+        }
+        let field = match target {
+            Value::Struct { name: _, ref fields } => fields.iter().find(|f| f.0 == name).map(|f| f.1.clone()),
+            Value::Reflect(ref r) => r.member(&name, pool),
+            _ => None,
+        };
+        if let Some(field) = field {
+            return Ok(field);
+        }
+        match self.method {
+            Some(ref method) => match eval.entity_value(&name, Some(method), &span)? {
+                Value::Function(fun) => Ok(Value::Function(Function::Bound {
+                    fun: fun.into(),
+                    this: target.into(),
+                })),
+                other => Ok(other),
+            },
+            None => Err(Interrupt::Error(
+                format!("Field '{name}' has no value at compile time"),
+                span
+            )),
+        }
+    }
+}
+
+impl MemberNode {
+    pub(crate) fn is_optional(&self) -> bool {
+        self.question.is_some()
+    }
+    /// Whether this accesses the struct field declared at `decl_span`
+    pub(crate) fn refers_to(&self, decl_span: &ArcSpan) -> bool {
+        self.field.as_ref().is_some_and(|f| f.decl_span == *decl_span)
+    }
+    /// The declaration of the computed property this refers to, if any
+    fn property(&self, eval: &Evaluator) -> Option<StructField> {
+        let field = self.field.as_ref().filter(|f| f.accessors.is_some())?;
+        eval.property(&field.decl_span)
+    }
+    fn eval_assign(&self, pool: &NodePool, eval: &mut Evaluator, value: Value, span: ArcSpan) -> Result<(), Interrupt> {
+        let mut target = self.target.eval_ref(pool, eval)?;
+        if let Some(property) = self.property(eval) {
+            return property.get(pool).eval_set(pool, eval, target, value, span).map(|_| ());
+        }
+        // The modified value is stored back where it came from
+        let field = match (&mut target, &*self.name.get(pool)) {
+            (Value::Struct { name: _, fields }, MemberNameNode::Ident(name)) => {
+                let name = name.get(pool).to_string();
+                fields.iter_mut().find(|f| f.0 == name).map(|f| &mut f.1)
+            }
+            _ => None,
+        };
+        match field {
+            Some(field) => {
+                *field = value;
+                eval_assign(self.target, target, pool, eval, span)
+            }
+            None => Err(Interrupt::Error(
+                format!("Fields of {} can not be assigned to at compile time", target.kind()),
+                span
+            )),
+        }
+    }
+}
+
+/// Whether `expr` is just the name of an item, possibly in parentheses
+fn is_variable(expr: Expr, pool: &NodePool) -> bool {
+    match &*expr.get(pool) {
+        ExprNode::Scalar(scalar) => match *scalar.get(pool) {
+            ScalarExprNode::Atom(atom) => match *atom.get(pool) {
+                AtomNode::ClosedExpr(inner) => is_variable(inner.get(pool).value, pool),
+                AtomNode::ItemUse(_) => true,
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Check that `expr` refers to something that can be assigned to, like a 
+/// variable or a field, and log an error if it doesn't
+pub(crate) fn check_assignable(expr: Expr, pool: &NodePool, checker: &mut Checker) -> bool {
+    let span = expr.get(pool).span_or_builtin(pool);
+    let (msg, span, note) = match &*expr.get(pool) {
+        ExprNode::Member(member) => {
+            let member = member.get(pool);
+            let name_span = member.name.get(pool).span_or_builtin(pool);
+            match (&member.field, &member.method) {
+                (Some(field), _) => match field.accessors {
+                    Some(accessors) if !accessors.set => (
+                        format!("Property '{}' has no setter", field.name),
+                        name_span,
+                        Some(("Property declared here", Some(field.decl_span.clone())))
+                    ),
+                    _ => return true,
+                },
+                (None, Some(_)) => (String::from("Cannot assign to a method"), name_span, None),
+                // Invalid or spliced members have already been reported
+                (None, None) => return true,
+            }
+        }
+        ExprNode::Index(index) => return index.get(pool).check_assignable(pool, checker),
+        ExprNode::Scalar(scalar) => match *scalar.get(pool) {
+            ScalarExprNode::Atom(atom) => match *atom.get(pool) {
+                AtomNode::ClosedExpr(inner) => return check_assignable(inner.get(pool).value, pool, checker),
+                AtomNode::ItemUse(item) => match &*item.get(pool) {
+                    ItemUseNode::Ident(name, _, Some(ent)) if ent.read_only() => (
+                        format!("Cannot assign to parameter {}", name.get(pool).to_path(pool)),
+                        span,
+                        Some(("Declare the parameter with 'var' to assign to it", Some(ent.span())))
+                    ),
+                    // Variables and parameters can be assigned, as can 
+                    // variables provided by the host
+                    ItemUseNode::Ident(_, _, Some(ent)) if ent.ephemeral() ||
+                        (ent.external() && !matches!(ent.ty(), Ty::Function { .. } | Ty::Generic { .. })) => return true,
+                    ItemUseNode::Ident(name, _, _) => (
+                        format!("Cannot assign to {}", name.get(pool).to_path(pool)),
+                        span,
+                        Some(("Only variables and fields can be assigned to", None))
+                    ),
+                    ItemUseNode::This(_) => (String::from("Cannot assign to 'this'"), span, None),
+                },
+                _ => (String::from("Cannot assign to this expression"), span, None),
+            },
+            _ => (String::from("Cannot assign to this expression"), span, None),
+        },
+        _ => (String::from("Cannot assign to this expression"), span, None),
+    };
+    let msg = Message::new(Level::Error, msg, span.as_ref());
+    match note {
+        Some((note, Some(at))) => checker.logger().lock().unwrap().log(msg.note(Note::new_at(note, at.as_ref()))),
+        Some((note, None)) => checker.logger().lock().unwrap().log(msg.note(Note::new(note, false))),
+        None => checker.logger().lock().unwrap().log(msg),
+    }
+    false
+}
+
+/// Assign `value` to the place `expr` refers to at compile time
+fn eval_assign(expr: Expr, value: Value, pool: &NodePool, eval: &mut Evaluator, span: ArcSpan) -> Result<(), Interrupt> {
+    match &*expr.get(pool) {
+        ExprNode::Member(member) => return member.get(pool).eval_assign(pool, eval, value, span),
+        ExprNode::Index(index) => return index.get(pool).eval_assign(pool, eval, value, span),
+        ExprNode::Scalar(scalar) => if let ScalarExprNode::Atom(atom) = *scalar.get(pool) {
+            match *atom.get(pool) {
+                AtomNode::ClosedExpr(inner) => return eval_assign(inner.get(pool).value, value, pool, eval, span),
+                AtomNode::ItemUse(item) => {
+                    let name = item.get(pool).name(pool).to_string();
+                    if eval.assign(&name, value) {
+                        return Ok(());
+                    }
+                    return Err(Interrupt::Error(
+                        format!("'{name}' can not be assigned to at compile time"),
+                        span
+                    ));
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+    Err(Interrupt::Error("Assignment can not be used at compile time".into(), span))
+}
+
+/// Get the value of an optional, like `parent!`. Unwrapping `none` is an 
+/// error
+#[derive(Debug)]
+pub struct UnwrapNode {
+    target: Expr,
+    bang: op::Not,
+}
+pub type Unwrap = RefToNode<UnwrapNode>;
+
+impl UnwrapNode {
+    pub(crate) fn parse_with(
+        target: Expr,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            target,
+            bang: ParseRef::parse_ref(pool, src, tokenizer)?,
+        };
+        Ok(pool.add(res))
+    }
+}
+
+impl Node for UnwrapNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.target, &self.bang]
+    }
+}
+
+impl ResolveNode for UnwrapNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        if target.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        match target.reduce() {
+            Ty::Option { ty } => Some(ty.as_ref().clone()),
+            _ => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot unwrap a value of type {target}"),
+                    self.bang.get(pool).span_or_builtin(pool).as_ref()
+                ).note(Note::new("Only optional values can be unwrapped", false)));
                 Some(Ty::Invalid)
             }
         }
     }
 }
 
+impl EvalNode for UnwrapNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        match self.target.eval_ref(pool, eval)? {
+            Value::None => Err(Interrupt::Error(
+                "Unwrapped a value that was none".into(),
+                self.span_or_builtin(pool)
+            )),
+            value => Ok(value),
+        }
+    }
+}
+
+/// Test whether a value is of a type, like `parent is Node`. Used as the 
+/// condition of an `if`, this narrows a tested variable to that type inside 
+/// the branch
+#[derive(Debug)]
+pub struct IsNode {
+    target: Expr,
+    is_kw: kw::Is,
+    ty: TypeExpr,
+}
+pub type Is = RefToNode<IsNode>;
+
+impl IsNode {
+    pub(crate) fn parse_with(
+        target: Expr,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            target,
+            is_kw: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            ty: ParseRef::parse_ref(pool, src, tokenizer)?,
+        };
+        Ok(pool.add(res))
+    }
+}
+
+impl Node for IsNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.target, &self.is_kw, &self.ty]
+    }
+}
+
+impl ResolveNode for IsNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        let ty = self.ty.try_resolve_ref(pool, checker)?;
+        if !target.is_unreal() && !ty.is_unreal() && !ty.convertible(&target) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("A value of type {target} can never be of type {ty}"),
+                self.span_or_builtin(pool).as_ref()
+            ));
+        }
+        Some(Ty::Bool)
+    }
+}
+
+impl EvalNode for IsNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let target = self.target.eval_ref(pool, eval)?;
+        let ty = self.ty.resolved_ty(pool).unwrap_or(Ty::Invalid);
+        Ok(Value::Bool(target.is_of(&ty)))
+    }
+}
+
+/// Convert a value to another type, like `size as Point`. Uses a conversion 
+/// declared with `fun (value: From) as To`, or one of the builtin numeric 
+/// conversions
+#[derive(Debug)]
+pub struct CastNode {
+    target: Expr,
+    as_kw: kw::As,
+    ty: TypeExpr,
+    /// The user-defined conversion used, if any
+    conversion: Option<Entity>,
+}
+pub type Cast = RefToNode<CastNode>;
+
+impl CastNode {
+    pub(crate) fn parse_with(
+        target: Expr,
+        pool: &mut NodePool,
+        src: Arc<Src>,
+        tokenizer: &mut TokenIterator
+    ) -> Result<NodeID, FatalParseError> {
+        let res = Self {
+            target,
+            as_kw: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            ty: ParseRef::parse_ref(pool, src, tokenizer)?,
+            conversion: None,
+        };
+        Ok(pool.add(res))
+    }
+}
+
+impl Node for CastNode {
+    fn children(&self) -> Vec<&dyn ResolveRef> {
+        vec![&self.target, &self.as_kw, &self.ty]
+    }
+}
+
+/// Call a user-defined conversion or operator with its operands
+fn call_overload(
+    name: &str,
+    entity: &Entity,
+    operands: Vec<Value>,
+    pool: &NodePool,
+    eval: &mut Evaluator,
+    span: ArcSpan
+) -> EvalResult {
+    match eval.entity_value(name, Some(entity), &span)? {
+        Value::Function(fun) => eval.call(pool, fun, operands.into_iter().map(|v| (None, v)).collect(), span),
+        other => Err(Interrupt::Error(format!("Cannot call a value of type {}", other.kind()), span)),
+    }
+}
+
+/// The operator function found for an operation, if it was declared in code 
+/// rather than being one of the builtin operators
+fn user_overload(entity: &Entity) -> Option<Entity> {
+    (entity.span() != ArcSpan::builtin()).then(|| entity.clone())
+}
+
+/// Whether a value of type `from` can be converted to `to` without a 
+/// user-defined conversion
+fn is_builtin_conversion(from: &Ty, to: &Ty) -> bool {
+    from.convertible(to) || matches!((from.reduce(), to.reduce()), (Ty::Int, Ty::Float) | (Ty::Float, Ty::Int))
+}
+
+impl ResolveNode for CastNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        let ty = self.ty.try_resolve_ref(pool, checker)?;
+        if target.is_unreal() || ty.is_unreal() {
+            return Some(ty);
+        }
+        let name = path::IdentPath::new([path::Ident::Conversion(target.clone(), ty.clone())], false);
+        self.conversion = checker.scopes().find_map(|s| s.entities().find(&name).cloned());
+        if self.conversion.is_none() && !is_builtin_conversion(&target, &ty) {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot convert from type {target} to {ty}"),
+                self.span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                format!("Conversions can be declared as 'fun (value: {target}) as {ty}'"),
+                true
+            )));
+        }
+        Some(ty)
+    }
+}
+
+impl EvalNode for CastNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let value = self.target.eval_ref(pool, eval)?;
+        let span = self.span_or_builtin(pool);
+        if let Some(ref conversion) = self.conversion {
+            return call_overload("as", conversion, vec![value], pool, eval, span);
+        }
+        Ok(match value {
+            Value::Int(i) if self.ty.resolved_ty(pool).is_some_and(|t| *t.reduce() == Ty::Float) => Value::Float(i as f64),
+            Value::Float(f) if self.ty.resolved_ty(pool).is_some_and(|t| *t.reduce() == Ty::Int) => Value::Int(f as i64),
+            other => other,
+        })
+    }
+}
+
+/// The variables whose types are narrowed when `cond` is true, along with 
+/// the type they are narrowed to. Only plain variables tested with `is` are 
+/// narrowed, including ones joined with `&&`
+pub(crate) fn narrowings(cond: Expr, pool: &NodePool) -> Vec<(path::IdentPath, Ty, ArcSpan)> {
+    match &*cond.get(pool) {
+        ExprNode::Is(is) => {
+            let is = is.get(pool);
+            let Some(ty) = is.ty.resolved_ty(pool).filter(|t| !t.is_unreal()) else {
+                return Vec::new();
+            };
+            match variable_of(is.target, pool) {
+                Some(name) => vec![(name, ty, is.span_or_builtin(pool))],
+                None => Vec::new(),
+            }
+        }
+        ExprNode::BinOp(binop) => {
+            let binop = binop.get(pool);
+            if binop.op.get(pool).op() != op::BinaryOp::And {
+                return Vec::new();
+            }
+            let mut res = narrowings(binop.lhs, pool);
+            res.extend(narrowings(binop.rhs, pool));
+            res
+        }
+        ExprNode::Scalar(scalar) => match *scalar.get(pool) {
+            ScalarExprNode::Atom(atom) => match *atom.get(pool) {
+                AtomNode::ClosedExpr(inner) => narrowings(inner.get(pool).value, pool),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// The name of the variable `expr` refers to, if it's just a variable
+fn variable_of(expr: Expr, pool: &NodePool) -> Option<path::IdentPath> {
+    let ExprNode::Scalar(scalar) = *expr.get(pool) else { return None };
+    let ScalarExprNode::Atom(atom) = *scalar.get(pool) else { return None };
+    match *atom.get(pool) {
+        AtomNode::ClosedExpr(inner) => variable_of(inner.get(pool).value, pool),
+        AtomNode::ItemUse(item) => match &*item.get(pool) {
+            ItemUseNode::Ident(name, _, Some(ent)) if ent.ephemeral() => Some(name.get(pool).to_path(pool)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub struct IndexNode {
     target: Expr,
     index: delim::Bracketed<Expr>,
-    trailing_comma: Option<punct::Comma>,
+    /// The user-defined indexing function used, if any
+    overload: Option<Entity>,
 }
 pub type Index = RefToNode<IndexNode>;
 
@@ -177,22 +938,139 @@ impl IndexNode {
     ) -> Result<NodeID, FatalParseError> {
         let res = Self {
             target,
-            index: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
-            trailing_comma: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
+            index: ParseRef::parse_ref(pool, src, tokenizer)?,
+            overload: None,
         };
         Ok(pool.add(res))
     }
+    /// Whether `target[index] = value` can be assigned, which is the case 
+    /// for items of lists and maps that can be assigned themselves
+    fn check_assignable(&self, pool: &NodePool, checker: &mut Checker) -> bool {
+        if self.overload.is_none() {
+            return check_assignable(self.target, pool, checker);
+        }
+        checker.logger().lock().unwrap().log(Message::new(
+            Level::Error,
+            "Cannot assign to the result of a user-defined index",
+            self.span_or_builtin(pool).as_ref()
+        ));
+        false
+    }
+    fn eval_assign(&self, pool: &NodePool, eval: &mut Evaluator, value: Value, span: ArcSpan) -> Result<(), Interrupt> {
+        let mut target = self.target.eval_ref(pool, eval)?;
+        let index = self.index.eval_ref(pool, eval)?;
+        if let Value::Map(entries) = &mut target {
+            // Assigning `none` to an entry removes it
+            let pos = entries.iter().position(|e| e.0 == index);
+            match (pos, value) {
+                (Some(pos), Value::None) => { entries.remove(pos); }
+                (Some(pos), value) => entries[pos].1 = value,
+                (None, Value::None) => {}
+                (None, value) => entries.push((index, value)),
+            }
+        }
+        else {
+            *list_item(&mut target, &index, span.clone())? = value;
+        }
+        eval_assign(self.target, target, pool, eval, span)
+    }
+}
+
+/// The item of a list value at `index`, erroring if it is out of bounds
+fn list_item<'v>(list: &'v mut Value, index: &Value, span: ArcSpan) -> Result<&'v mut Value, Interrupt> {
+    match (list, index) {
+        (Value::List(items), Value::Int(i)) => {
+            let len = items.len();
+            usize::try_from(*i).ok()
+                .and_then(|i| items.get_mut(i))
+                .ok_or_else(|| Interrupt::Error(
+                    format!("Index {i} is out of bounds for a list of length {len}"),
+                    span
+                ))
+        }
+        (list, index) => Err(Interrupt::Error(
+            format!("Indexing {} with {} can not be done at compile time", list.kind(), index.kind()),
+            span
+        )),
+    }
 }
 
 impl Node for IndexNode {
     fn children(&self) -> Vec<&dyn ResolveRef> {
-        vec![&self.target, &self.index, &self.trailing_comma]
+        vec![&self.target, &self.index]
     }
 }
 
 impl ResolveNode for IndexNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        todo!()
+        let target = self.target.try_resolve_ref(pool, checker)?;
+        let index = self.index.try_resolve_ref(pool, checker)?;
+        if target.is_unreal() || index.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        if let Some(fun) = operators::resolve_index(checker, &target, &index) {
+            self.overload = Some(fun.clone());
+            return match fun.ty() {
+                Ty::Function { params: _, ret_ty } => Some(*ret_ty),
+                other => ice!(
+                    "encountered entity for index '{target}[{index}]' that wasn't a function type, but {other}"
+                ),
+            };
+        }
+        match target.reduce() {
+            Ty::List { ty } => {
+                if *index.reduce() != Ty::Int {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("List indices must be of type int, got {index}"),
+                        self.index.get(pool).span_or_builtin(pool).as_ref()
+                    ));
+                }
+                Some(ty.as_ref().clone())
+            }
+            // Maps may not have an entry for the key
+            Ty::Map { key, value } => {
+                if !index.convertible(key) {
+                    checker.logger().lock().unwrap().log(Message::new(
+                        Level::Error,
+                        format!("Map keys are of type {key}, got {index}"),
+                        self.index.get(pool).span_or_builtin(pool).as_ref()
+                    ));
+                }
+                Some(value.as_ref().clone().into_optional())
+            }
+            _ => None,
+        }
+    }
+    fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
+        if let (Some(target), Some(index)) = (self.target.resolved_ty(pool), self.index.resolved_ty(pool)) {
+            logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot index a value of type {target} with {index}"),
+                self.span_or_builtin(pool).as_ref()
+            ).note(Note::new(
+                format!("Indexing can be declared as 'fun [](value: {target}, index: {index}) -> Item'"),
+                true
+            )))
+        }
+    }
+}
+
+impl EvalNode for IndexNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let mut target = self.target.eval_ref(pool, eval)?;
+        let index = self.index.eval_ref(pool, eval)?;
+        let span = self.span_or_builtin(pool);
+        if let Some(ref overload) = self.overload {
+            return call_overload("[]", overload, vec![target, index], pool, eval, span);
+        }
+        if let Value::Map(entries) = target {
+            return Ok(entries.into_iter()
+                .find(|e| e.0 == index)
+                .map(|e| e.1)
+                .unwrap_or(Value::None));
+        }
+        list_item(&mut target, &index, span).map(|i| i.clone())
     }
 }
 
@@ -200,6 +1078,8 @@ impl ResolveNode for IndexNode {
 pub struct UnOpNode {
     op: op::Unary,
     target: Expr,
+    /// The user-defined operator function used, if any
+    overload: Option<Entity>,
 }
 pub type UnOp = RefToNode<UnOpNode>;
 
@@ -215,6 +1095,7 @@ impl UnOpNode {
         let res = Self {
             op: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
             target: RefToNode::new_raw(target(pool, src, tokenizer)?),
+            overload: None,
         };
         Ok(pool.add(res))
     }
@@ -233,20 +1114,15 @@ impl ResolveNode for UnOpNode {
         if target.is_unreal() {
             return Some(Ty::Invalid);
         }
-        for scope in checker.scopes() {
-            let name = path::IdentPath::new([path::Ident::UnOp(op.op(), target.clone())], false);
-            if let Some(fun) = scope.entities().find(&name) {
-                match fun.ty() {
-                    Ty::Function { params: _, ret_ty } => return Some(ret_ty.as_ref().clone()),
-                    _ => ice!(
-                        "encountered entity with unop name '{name}' \
-                        that wasn't a function type, but {}",
-                        fun.ty()
-                    )
-                }
-            }
+        let fun = operators::resolve_unop(checker, op.op(), &target)?;
+        self.overload = user_overload(&fun);
+        match fun.ty() {
+            Ty::Function { params: _, ret_ty } => Some(*ret_ty),
+            _ => ice!(
+                "encountered entity for unop '{}{target}' that wasn't a function type, but {}",
+                op.op(), fun.ty()
+            )
         }
-        None
     }
     fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
         if let Some(target) = self.target.resolved_ty(pool) {
@@ -262,11 +1138,33 @@ impl ResolveNode for UnOpNode {
     }
 }
 
+impl EvalNode for UnOpNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let target = self.target.eval_ref(pool, eval)?;
+        let op = self.op.get(pool).op();
+        if let Some(ref overload) = self.overload {
+            return call_overload(&op.to_string(), overload, vec![target], pool, eval, self.span_or_builtin(pool));
+        }
+        Ok(match (op, target) {
+            (op::UnaryOp::Not, Value::Bool(b)) => Value::Bool(!b),
+            (op::UnaryOp::Neg, Value::Int(i)) => Value::Int(i.wrapping_neg()),
+            (op::UnaryOp::Neg, Value::Float(f)) => Value::Float(-f),
+            (op::UnaryOp::Plus, v @ (Value::Int(_) | Value::Float(_))) => v,
+            (op, other) => return Err(Interrupt::Error(
+                format!("Operator '{op}' on {} can not be used at compile time", other.kind()),
+                self.span_or_builtin(pool)
+            )),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct BinOpNode {
     lhs: Expr,
     op: op::Binary,
     rhs: Expr,
+    /// The operator function used, if the operation isn't an assignment
+    resolution: Option<BinOpResolution>,
 }
 pub type BinOp = RefToNode<BinOpNode>;
 
@@ -284,11 +1182,105 @@ impl BinOpNode {
             lhs,
             op: ParseRef::parse_ref(pool, src.clone(), tokenizer)?,
             rhs: RefToNode::new_raw(rhs(pool, src, tokenizer)?),
+            resolution: None,
         };
         Ok(pool.add(res))
     }
 }
 
+impl BinOpNode {
+    fn resolve_assign(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let target = checker.with_assignment(is_variable(self.lhs, pool), |checker| {
+            self.lhs.try_resolve_ref(pool, checker)
+        })?;
+        let value = checker.with_expected_ty(checker.decided_ty(&target), |checker| {
+            self.rhs.try_resolve_ref(pool, checker)
+        })?;
+        // Assigning to a variable declared without a type decides its type
+        if target.is_undecided() {
+            checker.decide_ty(&target, value, self.rhs.get(pool).span_or_builtin(pool));
+            return Some(Ty::Void);
+        }
+        if target.is_unreal() || value.is_unreal() {
+            return Some(Ty::Void);
+        }
+        if check_assignable(self.lhs, pool, checker) {
+            checker.expect_ty_eq(target, value, self.rhs.get(pool).span(pool));
+        }
+        Some(Ty::Void)
+    }
+    /// Resolve the operands of a regular operation. A variable whose type 
+    /// hasn't been decided yet gets the type of the other operand, like `z` 
+    /// in `z + 1`
+    fn resolve_operands(&self, pool: &NodePool, checker: &mut Checker) -> Option<(Ty, Ty)> {
+        let a = self.lhs.try_resolve_ref(pool, checker);
+        let b = match (self.rhs.try_resolve_ref(pool, checker), &a) {
+            (Some(b), _) => b,
+            (None, Some(a)) => checker.with_expected_ty(Some(a.clone()), |checker| {
+                self.rhs.try_resolve_ref(pool, checker)
+            })?,
+            (None, None) => return None,
+        };
+        let a = match a {
+            Some(a) => a,
+            None => checker.with_expected_ty(Some(b.clone()), |checker| {
+                self.lhs.try_resolve_ref(pool, checker)
+            })?,
+        };
+        Some((a, b))
+    }
+    /// `a += b` assigns `a + b` to `a`
+    fn resolve_compound(&mut self, op: op::BinaryOp, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let target = self.lhs.try_resolve_ref(pool, checker)?;
+        let value = checker.with_expected_ty(Some(target.clone()), |checker| {
+            self.rhs.try_resolve_ref(pool, checker)
+        })?;
+        if target.is_unreal() || value.is_unreal() {
+            return Some(Ty::Void);
+        }
+        let resolution = operators::resolve_binop(checker, &target, op, &value)?;
+        let result = resolution.ret_ty();
+        self.resolution = Some(resolution);
+        if check_assignable(self.lhs, pool, checker) {
+            checker.expect_ty_eq(target, result, self.span(pool));
+        }
+        Some(Ty::Void)
+    }
+    /// `a ?? b` is the value of `a` if it isn't `none`, otherwise `b`
+    fn resolve_coalesce(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let lhs = self.lhs.try_resolve_ref(pool, checker)?;
+        let inner = match lhs.reduce() {
+            Ty::Option { ty } => Some(ty.as_ref().clone()),
+            _ => None,
+        };
+        let rhs = checker.with_expected_ty(inner.clone(), |checker| {
+            self.rhs.try_resolve_ref(pool, checker)
+        })?;
+        if lhs.is_unreal() || rhs.is_unreal() {
+            return Some(Ty::Invalid);
+        }
+        let Some(inner) = inner else {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot use '??' on a value of type {lhs}"),
+                self.lhs.get(pool).span_or_builtin(pool).as_ref()
+            ).note(Note::new("Only optional values can have a fallback", false)));
+            return Some(Ty::Invalid);
+        };
+        // `none ?? b` is just `b`
+        if inner.is_unreal() {
+            return Some(rhs);
+        }
+        // The fallback may be optional too, in which case so is the result
+        let res = match rhs.reduce() {
+            Ty::Option { ty: _ } => lhs,
+            _ => inner,
+        };
+        checker.expect_ty_eq(res.clone(), rhs, self.rhs.get(pool).span(pool));
+        Some(res)
+    }
+}
+
 impl Node for BinOpNode {
     fn children(&self) -> Vec<&dyn ResolveRef> {
         vec![&self.lhs, &self.op, &self.rhs]
@@ -297,30 +1289,24 @@ impl Node for BinOpNode {
 
 impl ResolveNode for BinOpNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        let a = self.lhs.try_resolve_ref(pool, checker)?;
-        let b = self.rhs.try_resolve_ref(pool, checker)?;
-        let op = self.op.get(pool);
+        let op = self.op.get(pool).op();
+        match op {
+            op::BinaryOp::Seq => return self.resolve_assign(pool, checker),
+            op::BinaryOp::Coalesce => return self.resolve_coalesce(pool, checker),
+            _ => {}
+        }
+        if let Some(base) = op.compound_base() {
+            return self.resolve_compound(base, pool, checker);
+        }
+        let (a, b) = self.resolve_operands(pool, checker)?;
         if a.is_unreal() || b.is_unreal() {
             return Some(Ty::Invalid);
         }
-        for scope in checker.scopes() {
-            // todo: handle symmetrive ops, like a + b <=> b + a
-            // todo: synthesize ops, like a == b <=> a != b
-            let name = path::IdentPath::new([
-                path::Ident::BinOp(a.clone(), op.op(), b.clone())
-            ], false);
-            if let Some(fun) = scope.entities().find(&name) {
-                match fun.ty() {
-                    Ty::Function { params: _, ret_ty } => return Some(ret_ty.as_ref().clone()),
-                    _ => ice!(
-                        "encountered entity with binop name '{name}' \
-                        that wasn't a function type, but {}",
-                        fun.ty()
-                    )
-                }
-            }
-        }
-        None
+        let resolution = operators::resolve_binop(checker, &a, op, &b)?;
+        // Derived operators are the negation of their counterpart
+        let ty = if resolution.negated { Ty::Bool } else { resolution.ret_ty() };
+        self.resolution = Some(resolution);
+        Some(ty)
     }
     fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
         if let (Some(lhs), Some(rhs)) = (self.lhs.resolved_ty(pool), self.rhs.resolved_ty(pool)) {
@@ -335,3 +1321,367 @@ impl ResolveNode for BinOpNode {
         }
     }
 }
+
+/// Run one of the builtin binary operators on two values
+fn eval_binop(op: op::BinaryOp, a: Value, b: Value, span: ArcSpan) -> EvalResult {
+    use op::BinaryOp as Op;
+    Ok(match (op, a, b) {
+        (Op::Eq, Value::Int(a), Value::Int(b)) => Value::Bool(a == b),
+        (Op::Neq, Value::Int(a), Value::Int(b)) => Value::Bool(a != b),
+        (Op::Less, Value::Int(a), Value::Int(b)) => Value::Bool(a < b),
+        (Op::Leq, Value::Int(a), Value::Int(b)) => Value::Bool(a <= b),
+        (Op::Grt, Value::Int(a), Value::Int(b)) => Value::Bool(a > b),
+        (Op::Geq, Value::Int(a), Value::Int(b)) => Value::Bool(a >= b),
+        (Op::Add, Value::Int(a), Value::Int(b)) => Value::Int(a.wrapping_add(b)),
+        (Op::Sub, Value::Int(a), Value::Int(b)) => Value::Int(a.wrapping_sub(b)),
+        (Op::Mul, Value::Int(a), Value::Int(b)) => Value::Int(a.wrapping_mul(b)),
+        (Op::Div | Op::Mod, Value::Int(_), Value::Int(0)) => {
+            return Err(Interrupt::Error("Division by zero".into(), span));
+        }
+        (Op::Div, Value::Int(a), Value::Int(b)) => Value::Int(a.wrapping_div(b)),
+        (Op::Mod, Value::Int(a), Value::Int(b)) => Value::Int(a.wrapping_rem(b)),
+
+        (Op::Eq, Value::Float(a), Value::Float(b)) => Value::Bool(a == b),
+        (Op::Neq, Value::Float(a), Value::Float(b)) => Value::Bool(a != b),
+        (Op::Less, Value::Float(a), Value::Float(b)) => Value::Bool(a < b),
+        (Op::Leq, Value::Float(a), Value::Float(b)) => Value::Bool(a <= b),
+        (Op::Grt, Value::Float(a), Value::Float(b)) => Value::Bool(a > b),
+        (Op::Geq, Value::Float(a), Value::Float(b)) => Value::Bool(a >= b),
+        (Op::Add, Value::Float(a), Value::Float(b)) => Value::Float(a + b),
+        (Op::Sub, Value::Float(a), Value::Float(b)) => Value::Float(a - b),
+        (Op::Mul, Value::Float(a), Value::Float(b)) => Value::Float(a * b),
+        (Op::Div, Value::Float(a), Value::Float(b)) => Value::Float(a / b),
+        (Op::Mod, Value::Float(a), Value::Float(b)) => Value::Float(a % b),
+
+        (Op::Add, Value::Int(a), Value::Float(b)) => Value::Float(a as f64 + b),
+        (Op::Sub, Value::Int(a), Value::Float(b)) => Value::Float(a as f64 - b),
+        (Op::Mul, Value::Int(a), Value::Float(b)) => Value::Float(a as f64 * b),
+        (Op::Div, Value::Int(a), Value::Float(b)) => Value::Float(a as f64 / b),
+        (Op::Mod, Value::Int(a), Value::Float(b)) => Value::Int((a as f64 % b) as i64),
+        (Op::Mod, Value::Float(a), Value::Int(b)) => Value::Float(a % b as f64),
+
+        (Op::Eq, Value::String(a), Value::String(b)) => Value::Bool(a == b),
+        (Op::Neq, Value::String(a), Value::String(b)) => Value::Bool(a != b),
+        (Op::Add, Value::String(a), Value::String(b)) => Value::String(a + &b),
+        (Op::Mul, Value::String(a), Value::Int(b)) => Value::String(a.repeat(b.max(0) as usize)),
+
+        (op, a, b) => return Err(Interrupt::Error(
+            format!("Operator '{op}' on {} and {} can not be used at compile time", a.kind(), b.kind()),
+            span
+        )),
+    })
+}
+
+impl BinOpNode {
+    /// Run the operator function this operation was resolved to
+    fn eval_resolved(&self, op: op::BinaryOp, a: Value, b: Value, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let span = self.span_or_builtin(pool);
+        let Some(ref res) = self.resolution else {
+            return eval_binop(op, a, b, span);
+        };
+        let (a, b) = if res.swapped { (b, a) } else { (a, b) };
+        let value = match user_overload(&res.entity) {
+            Some(overload) => call_overload(&res.op.to_string(), &overload, vec![a, b], pool, eval, span.clone())?,
+            None => eval_binop(res.op, a, b, span.clone())?,
+        };
+        match (res.negated, value) {
+            (false, value) => Ok(value),
+            (true, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (true, other) => Err(Interrupt::Error(
+                format!("Operator '{op}' expected a bool to negate, got {}", other.kind()),
+                span
+            )),
+        }
+    }
+}
+
+impl EvalNode for BinOpNode {
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        let op = self.op.get(pool).op();
+        if op == op::BinaryOp::Seq {
+            let value = self.rhs.eval_ref(pool, eval)?;
+            eval_assign(self.lhs, value, pool, eval, self.span_or_builtin(pool))?;
+            return Ok(Value::Void);
+        }
+        if let Some(base) = op.compound_base() {
+            let a = self.lhs.eval_ref(pool, eval)?;
+            let b = self.rhs.eval_ref(pool, eval)?;
+            let value = self.eval_resolved(base, a, b, pool, eval)?;
+            eval_assign(self.lhs, value, pool, eval, self.span_or_builtin(pool))?;
+            return Ok(Value::Void);
+        }
+        let a = self.lhs.eval_ref(pool, eval)?;
+        if op == op::BinaryOp::Coalesce {
+            return match a {
+                Value::None => self.rhs.eval_ref(pool, eval),
+                a => Ok(a),
+            };
+        }
+        // `&&` and `||` only evaluate their right side if needed
+        match (op, &a) {
+            (op::BinaryOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+            (op::BinaryOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+            (op::BinaryOp::And | op::BinaryOp::Or, Value::Bool(_)) => return self.rhs.eval_ref(pool, eval),
+            _ => {}
+        }
+        let b = self.rhs.eval_ref(pool, eval)?;
+        self.eval_resolved(op, a, b, pool, eval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::{check_errors, check_errors_with_notes};
+
+    const POINT: &str = "struct Point {\n    x: int;\n    y: int;\n}\n\
+        fun Point::sum(this) -> int {\n    this.x + this.y\n}\n";
+
+    #[test]
+    fn fields_and_methods() {
+        assert_eq!(
+            check_errors(&format!("{POINT}fun test(p: Point) -> int {{\n    p.sum() + p.x\n}}\n")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn unknown_member() {
+        assert_eq!(
+            check_errors(&format!("{POINT}fun test(p: Point) -> int {{\n    p.z\n}}\n")),
+            ["Unknown member 'z' on type Point"]
+        );
+    }
+
+    #[test]
+    fn call_on_unknown_target() {
+        assert_eq!(
+            check_errors("fun test(a: int) -> int {\n    nothing.call(1)\n}\n"),
+            ["Unknown item nothing"]
+        );
+    }
+
+    #[test]
+    fn assignment() {
+        assert_eq!(
+            check_errors(&format!(
+                "{POINT}fun test(p: Point, var a: int) {{\n    let b = 1;\n    b = a;\n    a = p.sum();\n    p.x = b;\n}}\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!("{POINT}fun test(p: Point) {{\n    p.x = true;\n}}\n")),
+            ["Cannot convert from type bool to int"]
+        );
+    }
+
+    #[test]
+    fn assignment_to_read_only() {
+        assert_eq!(
+            check_errors("fun test(a: int) {\n    a = 2;\n}\n"),
+            ["Cannot assign to parameter a"]
+        );
+        assert_eq!(
+            check_errors(&format!("{POINT}fun test(p: Point) {{\n    p.sum = 2;\n    1 = 2;\n}}\n")),
+            ["Cannot assign to a method", "Cannot assign to this expression"]
+        );
+    }
+
+    #[test]
+    fn optionals() {
+        assert_eq!(
+            check_errors(&format!(
+                "{POINT}fun test(a: int?, p: Point?) -> int {{\n    let b: int? = none;\n    \
+                let s = p?.sum() ?? 0;\n    a! + (b ?? p?.x ?? s)\n}}\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!("{POINT}fun test(a: int, p: Point) -> int {{\n    a! + p?.x\n}}\n")),
+            ["Cannot unwrap a value of type int", "Cannot use '?.' on a value of type Point"]
+        );
+    }
+
+    const SIZE: &str = "struct Size {\n    width: int;\n    height: int;\n}\n";
+
+    #[test]
+    fn conversions() {
+        assert_eq!(
+            check_errors(&format!(
+                "{POINT}{SIZE}fun (p: Point) as Size {{\n    Size {{ width: p.x, height: p.y }}\n}}\n\
+                fun test(p: Point, a: int) -> float {{\n    let s = p as Size;\n    (s.width + a) as float\n}}\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!("{POINT}fun test(p: Point) -> string => p as string;\n")),
+            ["Cannot convert from type Point to string"]
+        );
+    }
+
+    #[test]
+    fn invalid_conversion_declarations() {
+        assert_eq!(
+            check_errors(&format!(
+                "{POINT}{SIZE}fun toSize(p: Point) as Size {{\n    Size {{ width: p.x, height: p.y }}\n}}\n"
+            )),
+            ["Conversions can not have a name"]
+        );
+        assert_eq!(
+            check_errors(&format!("{SIZE}fun (a: int, b: int) as Size {{\n    Size {{ width: a, height: b }}\n}}\n")),
+            ["Conversions must take exactly one parameter"]
+        );
+    }
+
+    const V: &str = "struct V {\n    x: int;\n}\n";
+
+    #[test]
+    fn operator_overloads() {
+        assert_eq!(
+            check_errors(&format!(
+                "{V}fun +(a: V, b: V) -> V => a;\nfun +(a: V, b: int) -> V => a;\nfun -(a: V) -> V => a;\n\
+                fun test(a: V) -> V => -(a + a + 1);\n"
+            )),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn conflicting_overloads() {
+        assert_eq!(
+            check_errors(&format!("{V}fun +(a: V, b: V) -> V => a;\nfun +(a: V, b: V) -> V => b;\n")),
+            ["Name binop`V+V` has already been defined"]
+        );
+        assert_eq!(
+            check_errors("fun +(a: int, b: int) -> int => a;\n"),
+            ["Name binop`int+int` has already been defined"]
+        );
+    }
+
+    #[test]
+    fn invalid_overloads() {
+        assert_eq!(
+            check_errors(&format!("{V}fun *(a: V) -> V => a;\nfun &&(a: V, b: V) -> V => a;\n")),
+            ["Operator '*' must take two parameters", "Operator '&&' can not be overloaded"]
+        );
+    }
+
+    #[test]
+    fn commutative_and_derived_operators() {
+        assert_eq!(
+            check_errors(&format!(
+                "{V}commutative fun *(a: V, b: int) -> V => a;\nfun ==(a: V, b: V) -> bool => true;\n\
+                fun test(a: V) -> bool => 2 * a != a * 2;\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!(
+                "{V}commutative fun *(a: V, b: int) -> V => a;\nfun *(a: int, b: V) -> V => b;\n"
+            )),
+            ["Name binop`int*V` has already been defined"]
+        );
+        assert_eq!(
+            check_errors(&format!("{V}commutative fun -(a: V) -> V => a;\n")),
+            ["Only binary operators can be commutative"]
+        );
+    }
+
+    #[test]
+    fn compound_assignment() {
+        assert_eq!(
+            check_errors(&format!(
+                "{V}fun +(a: V, b: int) -> V => a;\nfun test(var a: int, var v: V) {{\n    a += 2;\n    v += a;\n}}\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors("fun test(a: int) {\n    a *= 2;\n    1 += 2;\n}\n"),
+            ["Cannot assign to parameter a", "Cannot assign to this expression"]
+        );
+    }
+
+    #[test]
+    fn inferred_variable_types() {
+        assert_eq!(
+            check_errors(
+                "fun test(flag: bool) -> int {\n    let a;\n    if flag {\n        a = 1;\n    }\n    \
+                let b;\n    let c: int = b;\n    a + b\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors_with_notes("fun test() {\n    let a;\n    a = 1;\n    a = \"a\";\n    let b;\n    b.x;\n}\n"),
+            [
+                vec![
+                    "Cannot convert from type string to int",
+                    "The type of a was decided to be int here",
+                    "Declaration of a here",
+                ],
+                vec![
+                    "The type of b needs to be known at this point",
+                    "Declaration of b here",
+                    "Assign a value to b before this, or give it a type when declaring it",
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_and_indexing() {
+        assert_eq!(
+            check_errors(
+                "fun test(var list: [int]) -> int {\n    list.push(3);\n    let empty: [string] = [];\n    \
+                [1, 2][0] + list[1]\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(
+                "fun test(list: [int]) -> int {\n    list.push(3);\n    let a = [1, true];\n    \
+                list[true] + 5[0]\n}\n"
+            ),
+            [
+                "Cannot assign to parameter list",
+                "Cannot convert from type bool to int",
+                "List indices must be of type int, got bool",
+                "Cannot index a value of type int with int",
+            ]
+        );
+    }
+
+    #[test]
+    fn index_overloads() {
+        assert_eq!(
+            check_errors(&format!("{V}fun [](v: V, index: string) -> int => 1;\nfun test(v: V) -> int => v[\"a\"];\n")),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(&format!("{V}fun [](v: V) -> int => 1;\nfun test(v: V) -> int => v[1];\n")),
+            ["Operator '[]' must take two parameters", "Cannot index a value of type V with int"]
+        );
+    }
+
+    #[test]
+    fn maps() {
+        assert_eq!(
+            check_errors(
+                "fun test(ids: [string: int]) -> int {\n    let names = [1: \"a\", 2: \"b\"];\n    \
+                let empty: [bool: int] = [:];\n    for entry in names {\n        ids[entry.value];\n    }\n    \
+                ids[\"a\"] ?? 0\n}\n"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_errors(
+                "fun test(ids: [string: int], points: [[int]: int]) -> int {\n    let a = [1: 2, 3];\n    \
+                ids[1]\n}\n"
+            ),
+            [
+                "Type [int] can not be used as a map key",
+                "Expected a value for this key",
+                "Map keys are of type string, got int",
+                "Cannot convert from type int? to int",
+            ]
+        );
+    }
+}
@@ -17,6 +17,8 @@ pub(crate) mod kw {
 
     #[token(kind = "Keyword", raw = "let")]
     pub struct Let {}
+    #[token(kind = "Keyword", raw = "var")]
+    pub struct Var {}
     #[token(kind = "Keyword", raw = "fun")]
     pub struct Fun {}
     #[token(kind = "Keyword", raw = "if")]
@@ -27,19 +29,55 @@ pub(crate) mod kw {
     pub struct This {}
     #[token(kind = "Keyword", raw = "return")]
     pub struct Return {}
+    #[token(kind = "Keyword", raw = "for")]
+    pub struct For {}
+    #[token(kind = "Keyword", raw = "in")]
+    pub struct In {}
+    #[token(kind = "Keyword", raw = "while")]
+    pub struct While {}
+    #[token(kind = "Keyword", raw = "break")]
+    pub struct Break {}
+    #[token(kind = "Keyword", raw = "continue")]
+    pub struct Continue {}
     #[token(kind = "Keyword", raw = "using")]
     pub struct Using {}
+    #[token(kind = "Keyword", raw = "struct")]
+    pub struct Struct {}
+    #[token(kind = "Keyword", raw = "enum")]
+    pub struct Enum {}
+    #[token(kind = "Keyword", raw = "extern")]
+    pub struct Extern {}
+    #[token(kind = "Keyword", raw = "public")]
+    pub struct Public {}
+    #[token(kind = "Keyword", raw = "macro")]
+    pub struct Macro {}
+    #[token(kind = "Keyword", raw = "codegen")]
+    pub struct Codegen {}
+    #[token(kind = "Keyword", raw = "depends")]
+    pub struct Depends {}
+    #[token(kind = "Keyword", raw = "is")]
+    pub struct Is {}
+    #[token(kind = "Keyword", raw = "as")]
+    pub struct As {}
 
     #[token(kind = "Ident", raw = "get")]
     pub struct Get {}
     #[token(kind = "Ident", raw = "set")]
     pub struct Set {}
+    #[token(kind = "Ident", raw = "decl")]
+    pub struct Decl {}
+    #[token(kind = "Ident", raw = "commutative")]
+    pub struct Commutative {}
 }
 
 pub(crate) mod lit {
     use dash_macros::{token, ParseNode};
 
-    use crate::{checker::{resolve::ResolveNode, coherency::Checker, ty::Ty}, parser::parse::NodePool};
+    use crate::{
+        checker::{resolve::ResolveNode, coherency::Checker, ty::Ty},
+        eval::{EvalNode, EvalResult, Evaluator, value::Value},
+        parser::parse::NodePool
+    };
 
     #[token(kind = "Keyword", raw = "void", no_default_resolve)]
     pub struct Void {}
@@ -50,6 +88,34 @@ pub(crate) mod lit {
         }
     }
 
+    impl EvalNode for VoidNode {
+        fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+            Ok(Value::Void)
+        }
+    }
+
+    /// The `none` literal. Not called `None` so it doesn't shadow 
+    /// `Option::None`
+    #[token(kind = "Keyword", raw = "none", no_default_resolve)]
+    pub struct NoneLit {}
+
+    impl ResolveNode for NoneLitNode {
+        fn try_resolve_node(&mut self, _: &NodePool, checker: &mut Checker) -> Option<Ty> {
+            // `none` is of the optional type expected of it, or otherwise 
+            // `never?` which converts to any other optional
+            Some(match checker.expected_ty() {
+                Some(ty) if !ty.is_unreal() => ty.into_optional(),
+                _ => Ty::Option { ty: Ty::Never.into() },
+            })
+        }
+    }
+
+    impl EvalNode for NoneLitNode {
+        fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+            Ok(Value::None)
+        }
+    }
+
     #[token(kind = "Keyword", raw = "true")]
     pub struct True {}
 
@@ -69,6 +135,12 @@ pub(crate) mod lit {
         }
     }
 
+    impl EvalNode for BoolNode {
+        fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+            Ok(Value::Bool(matches!(self, Self::True(_))))
+        }
+    }
+
     #[token(kind = "Int(_)", no_default_resolve)]
     pub struct Int {
         value: i64,
@@ -80,6 +152,12 @@ pub(crate) mod lit {
         }
     }
 
+    impl EvalNode for IntNode {
+        fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+            Ok(Value::Int(self.value))
+        }
+    }
+
     #[token(kind = "Float(_)", no_default_resolve)]
     pub struct Float {
         value: f64,
@@ -91,6 +169,12 @@ pub(crate) mod lit {
         }
     }
 
+    impl EvalNode for FloatNode {
+        fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+            Ok(Value::Float(self.value))
+        }
+    }
+
     #[token(kind = "String(_)", no_default_resolve)]
     pub struct String {
         value: std::string::String,
@@ -101,6 +185,12 @@ pub(crate) mod lit {
             Some(Ty::String)
         }
     }
+
+    impl EvalNode for StringNode {
+        fn eval_node(&self, _: &NodePool, _: &mut Evaluator) -> EvalResult {
+            Ok(Value::String(self.value.clone()))
+        }
+    }
 }
 
 pub(crate) mod punct {
@@ -192,6 +282,9 @@ pub(crate) mod punct {
     #[token(kind = "Punct", raw = "::")]
     pub struct Namespace {}
 
+    #[token(kind = "Punct", raw = ".")]
+    pub struct Dot {}
+
     #[token(kind = "Punct", raw = "->")]
     pub struct Arrow {}
 
@@ -200,6 +293,9 @@ pub(crate) mod punct {
 
     #[token(kind = "Punct", raw = "@")]
     pub struct At {}
+
+    #[token(kind = "Punct", raw = "<=>")]
+    pub struct Bind {}
 }
 
 pub(crate) mod op {
@@ -292,6 +388,38 @@ pub(crate) mod op {
             Add = "+", Sub = "-",
             Mul = "*", Div = "/", Mod = "%",
             Grt = ">", Geq = ">=", Less = "<", Leq = "<=",
+            Coalesce = "??",
+            AddSeq = "+=", SubSeq = "-=",
+            MulSeq = "*=", DivSeq = "/=", ModSeq = "%=",
+        }
+    }
+
+    impl BinaryOp {
+        /// The operator applied by a compound assignment, like `+` for `+=`
+        pub fn compound_base(&self) -> Option<BinaryOp> {
+            match self {
+                Self::AddSeq => Some(Self::Add),
+                Self::SubSeq => Some(Self::Sub),
+                Self::MulSeq => Some(Self::Mul),
+                Self::DivSeq => Some(Self::Div),
+                Self::ModSeq => Some(Self::Mod),
+                _ => None,
+            }
+        }
+        /// The operator this one is the negation of if it isn't declared, 
+        /// like `==` for `!=`
+        pub fn derived_from(&self) -> Option<BinaryOp> {
+            match self {
+                Self::Neq => Some(Self::Eq),
+                Self::Leq => Some(Self::Grt),
+                Self::Geq => Some(Self::Less),
+                _ => None,
+            }
+        }
+        /// Whether this operator can be implemented by a function. Assignments 
+        /// and short-circuiting operators have builtin behaviour
+        pub fn is_overloadable(&self) -> bool {
+            !matches!(self, Self::Seq | Self::And | Self::Or | Self::Coalesce) && self.compound_base().is_none()
         }
     }
 
@@ -303,12 +431,13 @@ pub(crate) mod op {
         Eq,
         And,
         Or,
+        Coalesce,
         Seq,
     }
 
     impl Prec {
-        pub(crate) const fn order() -> [Prec; 7] {
-            [Prec::Mul, Prec::Add, Prec::Ord, Prec::Eq, Prec::And, Prec::Or, Prec::Seq]
+        pub(crate) const fn order() -> [Prec; 8] {
+            [Prec::Mul, Prec::Add, Prec::Ord, Prec::Eq, Prec::And, Prec::Or, Prec::Coalesce, Prec::Seq]
         }
         pub fn peek(&self, tokenizer: &TokenIterator) -> bool {
             match self {
@@ -320,7 +449,10 @@ pub(crate) mod op {
                 Prec::Eq  => Eq::peek(0, tokenizer) || Neq::peek(0, tokenizer),
                 Prec::And => And::peek(0, tokenizer),
                 Prec::Or  => Or::peek(0, tokenizer),
-                Prec::Seq => Seq::peek(0, tokenizer),
+                Prec::Coalesce => Coalesce::peek(0, tokenizer),
+                Prec::Seq => Seq::peek(0, tokenizer) || AddSeq::peek(0, tokenizer) ||
+                             SubSeq::peek(0, tokenizer) || MulSeq::peek(0, tokenizer) ||
+                             DivSeq::peek(0, tokenizer) || ModSeq::peek(0, tokenizer),
             }
         }
     }
@@ -331,7 +463,8 @@ pub(crate) mod delim {
 
     use crate::{
         parser::parse::{NodePool, ParseRef},
-        checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty}
+        checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty},
+        eval::{EvalNode, EvalRef, EvalResult, Evaluator}
     };
 
     #[token(kind = "Parentheses(_)", value_is_token_tree, no_default_resolve)]
@@ -344,6 +477,12 @@ pub(crate) mod delim {
             self.value.try_resolve_ref(pool, checker)
         }
     }
+
+    impl<T: ResolveRef + ParseRef + EvalRef> EvalNode for ParenthesizedNode<T> {
+        fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+            self.value.eval_ref(pool, eval)
+        }
+    }
      
     #[token(kind = "Brackets(_)", value_is_token_tree, no_default_resolve)]
     pub struct Bracketed<T: ParseRef + ResolveRef> {
@@ -356,6 +495,12 @@ pub(crate) mod delim {
         }
     }
 
+    impl<T: ResolveRef + ParseRef + EvalRef> EvalNode for BracketedNode<T> {
+        fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+            self.value.eval_ref(pool, eval)
+        }
+    }
+
     #[token(kind = "Braces(_)", value_is_token_tree, no_default_resolve)]
     pub struct Braced<T: ParseRef + ResolveRef> {
         pub value: T,
@@ -367,6 +512,12 @@ pub(crate) mod delim {
         }
     }
 
+    impl<T: ResolveRef + ParseRef + EvalRef> EvalNode for BracedNode<T> {
+        fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+            self.value.eval_ref(pool, eval)
+        }
+    }
+
     /// Placeholder used for peeking delimiters
     #[derive(Debug, ParseNode)]
     pub struct PNode;
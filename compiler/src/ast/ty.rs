@@ -3,11 +3,11 @@ use std::sync::Arc;
 
 use dash_macros::{ParseNode, ResolveNode};
 use crate::{
-    parser::{parse::{ParseNode, FatalParseError, RefToNode, NodePool, Node, NodeID, ParseRef}, tokenizer::TokenIterator},
-    shared::{src::Src, logger::{Message, Level, LoggerRef}},
+    parser::{parse::{ParseNode, FatalParseError, RefToNode, NodePool, Node, NodeID, ParseRef, Separated, SeparatedWithTrailing}, tokenizer::TokenIterator},
+    shared::{src::Src, logger::{Message, Level, Note, LoggerRef}},
     checker::{resolve::{ResolveNode, ResolveRef}, coherency::Checker, ty::Ty}
 };
-use super::{expr::IdentPath, token::op};
+use super::{expr::IdentPath, token::{op, punct, delim, kw, lit, Ident}};
 
 #[derive(Debug)]
 pub enum TypeExprNode {
@@ -52,22 +52,149 @@ impl ResolveNode for TypeExprNode {
 #[derive(Debug, ParseNode, ResolveNode)]
 #[parse(expected = "type")]
 pub enum TypeAtomNode {
+    Function(FunctionType),
+    Collection(CollectionType),
+    Void(lit::Void),
     TypeIdent(TypeIdent),
 }
 
+#[derive(Debug, ParseNode)]
+pub struct FunctionTypeNode {
+    fun_kw: kw::Fun,
+    params: delim::Parenthesized<Option<SeparatedWithTrailing<TypeExpr, punct::Comma>>>,
+    arrow: punct::Arrow,
+    ret_ty: TypeExpr,
+}
+
+impl ResolveNode for FunctionTypeNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let params = self.params.get(pool).value.iter()
+            .flat_map(|p| p.iter())
+            .map(|p| p.try_resolve_ref(pool, checker))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Option<Vec<_>>>()?;
+        Some(Ty::Function {
+            params: params.into_iter().map(|p| (None, p)).collect(),
+            ret_ty: self.ret_ty.try_resolve_ref(pool, checker)?.into(),
+        })
+    }
+}
+
+/// A list type like `[int]` or a map type like `[string: int]`
+#[derive(Debug, ParseNode)]
+pub struct CollectionTypeNode {
+    ty: delim::Bracketed<(TypeExpr, Option<(punct::Colon, TypeExpr)>)>,
+}
+
+impl ResolveNode for CollectionTypeNode {
+    fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let (ty, value) = self.ty.get(pool).value;
+        let ty_span = ty.get(pool).span(pool);
+        let ty = ty.try_resolve_ref(pool, checker)?;
+        let Some((_, value)) = value else {
+            return Some(Ty::List { ty: ty.into() });
+        };
+        let value = value.try_resolve_ref(pool, checker)?;
+        checker.expect_ty_hashable(&ty, ty_span);
+        Some(Ty::Map { key: ty.into(), value: value.into() })
+    }
+}
+
+/// The type parameters of a generic declaration, like `<K, V>`
+#[derive(Debug, ParseNode)]
+pub struct TypeParamsNode {
+    open: op::Less,
+    params: Separated<Ident, punct::Comma>,
+    close: op::Grt,
+}
+
+impl TypeParamsNode {
+    pub(crate) fn params(&self, pool: &NodePool) -> Vec<Ty> {
+        self.params.iter()
+            .map(|p| Ty::Param {
+                name: p.get(pool).to_string(),
+                decl_span: p.get(pool).span_or_builtin(pool),
+            })
+            .collect()
+    }
+}
+
+impl ResolveNode for TypeParamsNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
+/// Explicit type arguments given to a generic item, like `<string, int>`
+#[derive(Debug, ParseNode)]
+pub struct TypeArgsNode {
+    open: op::Less,
+    args: Separated<TypeExpr, punct::Comma>,
+    close: op::Grt,
+}
+
+impl TypeArgsNode {
+    /// Give the generic type `ty` these arguments, logging an error if it 
+    /// isn't generic or takes a different amount of them
+    pub(crate) fn instantiate(&self, ty: &Ty, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        let args = self.args.iter()
+            .map(|a| a.try_resolve_ref(pool, checker))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Option<Vec<_>>>()?;
+        let span = self.span_or_builtin(pool);
+        let Ty::Generic { params, ty: _ } = ty else {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Type {ty} is not generic"),
+                span.as_ref()
+            ));
+            return Some(Ty::Invalid);
+        };
+        if params.len() != args.len() {
+            checker.logger().lock().unwrap().log(Message::new(
+                Level::Error,
+                format!(
+                    "Expected {} type argument{}, got {}",
+                    params.len(), if params.len() == 1 { "" } else { "s" }, args.len()
+                ),
+                span.as_ref()
+            ).note(Note::new(format!("Type parameters are {ty}"), false)));
+            return Some(Ty::Invalid);
+        }
+        Some(ty.instantiate(&args))
+    }
+}
+
+impl ResolveNode for TypeArgsNode {
+    fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+        Some(Ty::Invalid)
+    }
+}
+
 #[derive(Debug, ParseNode)]
 pub struct TypeIdentNode {
     name: IdentPath,
+    args: Option<TypeArgs>,
 }
 
 impl ResolveNode for TypeIdentNode {
     fn try_resolve_node(&mut self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
-        for scope in checker.scopes() {
-            if let Some(ty) = scope.types().find(&self.name.get(pool).to_path(pool)) {
-                return Some(ty.clone());
+        let ty = checker.scopes()
+            .find_map(|s| s.types().find(&self.name.get(pool).to_path(pool)).cloned())?;
+        match (ty, self.args) {
+            (ty, Some(args)) => args.get(pool).instantiate(&ty, pool, checker),
+            (ty @ Ty::Generic { .. }, None) => {
+                checker.logger().lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Generic type {ty} must be given type arguments"),
+                    self.name.get(pool).span_or_builtin(pool).as_ref()
+                ));
+                Some(Ty::Invalid)
             }
+            (ty, None) => Some(ty),
         }
-        None
     }
     fn log_unresolved_reason(&self, pool: &NodePool, _checker: &Checker, logger: LoggerRef) {
         logger.lock().unwrap().log(Message::new(
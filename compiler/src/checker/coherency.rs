@@ -1,12 +1,17 @@
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::{
     shared::{logger::{LoggerRef, Message, Level, Note}, src::{ArcSpan, Span}},
     ast::token::op,
-    parser::parse::NodePool,
-    checker::resolve::ResolveRef
+    parser::parse::{NodePool, Node},
+    checker::resolve::ResolveRef,
+    eval::{self, reflect::{self, Code}, list},
+    ast::{decl::DecoratedDecl, codegen}
 };
-use super::{ty::Ty, path::{FullIdentPath, IdentPath, Ident}, entity::Entity, pool::AST};
+use super::{ty::{Ty, Field}, path::{FullIdentPath, IdentPath, Ident}, entity::{Entity, Capture}, pool::ASTPool};
+
+// todo: allow customizing max expansion depth via a compiler option
+const MAX_EXPANSION_DEPTH: usize = 256;
 
 #[derive(Debug)]
 struct ItemSpace<T> {
@@ -42,6 +47,20 @@ impl<T> ItemSpace<T> {
             self.get(&name.to_full())
         }
     }
+    /// Find all items directly inside a namespace with an unresolved name
+    fn find_members(&self, namespace: &IdentPath, stack: &FullIdentPath) -> Vec<(&Ident, &T)> {
+        let mut res = Vec::new();
+        let mut temp = stack.clone();
+        loop {
+            let ns = temp.join(namespace);
+            res.extend(self.items.iter().filter_map(|(k, v)| k.member_of(&ns).map(|k| (k, v))));
+            if temp.is_empty() || namespace.is_absolute() {
+                break;
+            }
+            temp.pop();
+        }
+        res
+    }
     fn try_push(&mut self, name: &IdentPath, item: T, stack: &FullIdentPath) -> Result<&T, &T> {
         // The full name for this item is the current topmost namespace name 
         // joined with the name of the item
@@ -78,6 +97,10 @@ impl<'s, T> ItemSpaceWithStack<'s, T> {
     pub fn find(self, name: &IdentPath) -> Option<&'s T> {
         self.space.find(name, self.stack)
     }
+    /// Find all items directly inside a namespace with an unresolved name
+    pub fn find_members(self, namespace: &IdentPath) -> Vec<(&'s Ident, &'s T)> {
+        self.space.find_members(namespace, self.stack)
+    }
 }
 
 #[derive(Debug)]
@@ -95,35 +118,81 @@ impl<'s, T> ItemSpaceWithStackMut<'s, T> {
     pub fn try_push(self, name: &IdentPath, item: T) -> Result<&'s T, &'s T> {
         self.space.try_push(name, item, self.stack)
     }
+    /// Replace an item pushed with `try_push`
+    pub fn replace(self, name: &IdentPath, item: T) {
+        self.space.items.insert(self.stack.join(name), item);
+    }
+}
+
+/// What introduced a scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    /// A plain block, like the body of an `if`
+    Block,
+    /// The body of a function. Variables declared inside it are locals
+    Function,
+    /// The body of a closure, which captures the locals of enclosing 
+    /// functions that it uses
+    Closure,
+    /// The body of a loop, which can be exited with `break` and `continue`
+    Loop,
 }
 
 #[derive(Debug)]
 struct Scope {
     parent: Option<ScopeID>,
+    kind: ScopeKind,
     types: ItemSpace<Ty>,
     entities: ItemSpace<Entity>,
+    /// Entities captured by this scope if it's a closure
+    captures: Vec<Capture>,
+    /// The declared return type if this scope is the body of a function, or 
+    /// `None` if the return type is inferred
+    ret_ty: Option<Ty>,
+    /// The types of the values returned from this function if its return 
+    /// type is inferred, along with the spans of the `return`s
+    returns: Vec<(Ty, ArcSpan)>,
 }
 
 impl Scope {
-    fn new(parent: ScopeID) -> Self {
+    fn new(parent: ScopeID, kind: ScopeKind) -> Self {
         Self {
             parent: Some(parent),
+            kind,
             types: Default::default(),
             entities: Default::default(),
+            captures: Default::default(),
+            ret_ty: None,
+            returns: Default::default(),
         }
     }
     fn root() -> Self {
         macro_rules! decl_binop {
             ($a: ident $op: ident $b: ident => $r: ident) => {
-                (Ty::$a, op::BinaryOp::$op, Ty::$b, Ty::$r)
+                (Ty::$a, op::BinaryOp::$op, Ty::$b, Ty::$r, false)
+            };
+            ($a: ident $op: ident $b: ident => $r: ident, commutative) => {
+                (Ty::$a, op::BinaryOp::$op, Ty::$b, Ty::$r, true)
+            };
+        }
+        macro_rules! decl_unop {
+            ($op: ident $a: ident => $r: ident) => {
+                (op::UnaryOp::$op, Ty::$a, Ty::$r)
             };
         }
 
         Self {
             parent: None,
+            kind: ScopeKind::Block,
+            captures: Default::default(),
+            ret_ty: None,
+            returns: Default::default(),
             types: ItemSpace::new(
                 [Ty::Never, Ty::Void, Ty::Bool, Ty::Int, Ty::Float, Ty::String]
                     .map(|t| (FullIdentPath::new([t.to_string().into()]), t))
+                    .into_iter()
+                    .chain(reflect::builtin_types())
+                    .collect::<HashMap<_, _>>()
             ),
             entities: ItemSpace::new(
                 [
@@ -151,9 +220,9 @@ impl Scope {
                     decl_binop!(Float Div Float => Float),
                     decl_binop!(Float Mod Float => Float),
 
-                    decl_binop!(Int Add Float => Float),
+                    decl_binop!(Int Add Float => Float, commutative),
                     decl_binop!(Int Sub Float => Float),
-                    decl_binop!(Int Mul Float => Float),
+                    decl_binop!(Int Mul Float => Float, commutative),
                     decl_binop!(Int Div Float => Float),
                     decl_binop!(Int Mod Float => Int),
                     decl_binop!(Float Mod Int => Float),
@@ -161,12 +230,12 @@ impl Scope {
                     decl_binop!(String Eq String => Bool),
                     decl_binop!(String Neq String => Bool),
                     decl_binop!(String Add String => String),
-                    decl_binop!(String Mul Int => String),
+                    decl_binop!(String Mul Int => String, commutative),
 
                     decl_binop!(Bool And Bool => Bool),
                     decl_binop!(Bool Or Bool => Bool),
                 ]
-                .map(|(a, op, b, ret)| (
+                .map(|(a, op, b, ret, commutative)| (
                     FullIdentPath::new([Ident::BinOp(a.clone(), op, b.clone())]),
                     Entity::new(
                         Ty::Function {
@@ -175,23 +244,45 @@ impl Scope {
                         },
                         ArcSpan::builtin(),
                         false
-                    )
+                    ).with_commutative(commutative)
                 ))
+                .into_iter()
+                .chain([
+                    decl_unop!(Neg Int => Int),
+                    decl_unop!(Plus Int => Int),
+                    decl_unop!(Neg Float => Float),
+                    decl_unop!(Plus Float => Float),
+                    decl_unop!(Not Bool => Bool),
+                ]
+                .map(|(op, a, ret)| (
+                    FullIdentPath::new([Ident::UnOp(op, a.clone())]),
+                    Entity::new(
+                        Ty::Function {
+                            params: vec![(None, a)],
+                            ret_ty: Box::from(ret)
+                        },
+                        ArcSpan::builtin(),
+                        false
+                    )
+                )))
+                .chain(reflect::builtin_entities())
+                .collect::<HashMap<_, _>>()
             ),
         }
     }
-    fn drop_ephemeral(&mut self) {
-        self.entities.items.retain(|_, v| !v.ephemeral());
-    }
 }
 
 #[derive(Debug)]
 pub struct ScopeWithStack<'s> {
+    id: ScopeID,
     scope: &'s Scope,
     stack: &'s FullIdentPath,
 }
 
 impl<'s> ScopeWithStack<'s> {
+    pub fn id(&self) -> ScopeID {
+        self.id
+    }
     pub fn types(&self) -> ItemSpaceWithStack<'s, Ty> {
         ItemSpaceWithStack { space: &self.scope.types, stack: self.stack }
     }
@@ -253,17 +344,52 @@ impl<'s> ScopeIter<'s> {
 impl<'s> Iterator for ScopeIter<'s> {
     type Item = ScopeWithStack<'s>;
     fn next(&mut self) -> Option<Self::Item> {
-        let ret = self.scopes.get(self.current?.0).unwrap();
+        let id = self.current?;
+        let ret = self.scopes.get(id.0).unwrap();
         self.current = ret.parent;
-        Some(ScopeWithStack { scope: ret, stack: self.stack })
+        Some(ScopeWithStack { id, scope: ret, stack: self.stack })
     }
 }
 
+/// Where a node was checked, so that code generated for it later can be 
+/// checked as if it was written there
+#[derive(Debug, Clone)]
+pub struct Context {
+    scope: ScopeID,
+    namespace_stack: FullIdentPath,
+    struct_body: Option<Ty>,
+}
+
 pub struct Checker {
     logger: LoggerRef,
     current_scope: ScopeID,
     scopes: Vec<Scope>,
     namespace_stack: FullIdentPath,
+    /// The type the value currently being resolved is expected to have, if 
+    /// known
+    expected_ty: Option<Ty>,
+    /// Whether the declaration currently being resolved is `extern`
+    in_extern: bool,
+    /// The full types of the structs declared so far, by the span of their 
+    /// declaration. Fields that refer to the struct they are in only have 
+    /// its forward declaration, which has no fields
+    structs: HashMap<ArcSpan, Ty>,
+    /// The declaration spans of the structs that are provided by the host
+    extern_structs: HashSet<ArcSpan>,
+    /// The struct whose body the declarations being resolved are in
+    struct_body: Option<Ty>,
+    /// The type of the struct and the type of the property whose accessors 
+    /// are currently being resolved
+    property: Option<(Ty, Ty)>,
+    /// Whether the variable currently being resolved is being assigned to, 
+    /// in which case its type doesn't need to be decided yet
+    in_assignment: bool,
+    /// The types decided for variables declared without one, by the span of 
+    /// their declaration, along with the span of the value that decided it
+    decided: HashMap<ArcSpan, (Ty, ArcSpan)>,
+    /// The names and declaration spans of the functions whose return types 
+    /// are inferred from their bodies
+    inferred_funs: Vec<(IdentPath, ArcSpan)>,
     some_nodes_resolve_state_changed: bool,
 }
 
@@ -274,15 +400,91 @@ impl Checker {
             current_scope: ScopeID(0),
             scopes: Vec::from([Scope::root()]),
             namespace_stack: FullIdentPath::default(),
+            expected_ty: None,
+            in_extern: false,
+            structs: HashMap::new(),
+            extern_structs: HashSet::new(),
+            struct_body: None,
+            property: None,
+            in_assignment: false,
+            decided: HashMap::new(),
+            inferred_funs: Vec::new(),
             some_nodes_resolve_state_changed: false,
         }
     }
-    pub fn try_resolve(ast: &mut AST, pool: &mut NodePool, logger: LoggerRef) -> Ty {
+    /// Check all of the modules in the pool together, so that they can 
+    /// refer to each other's items
+    pub fn try_resolve(asts: &ASTPool, pool: &mut NodePool, logger: LoggerRef) -> Ty {
         let mut checker = Checker::new(logger.clone());
+        let resolved = checker.resolve_passes(pool, |checker, pool| {
+            let mut all_resolved = true;
+            for ast in asts {
+                for name in ast.namespace() {
+                    checker.enter_namespace(name.clone());
+                }
+                all_resolved &= ast.module().try_resolve_ref(pool, checker).is_some();
+                for _ in ast.namespace() {
+                    checker.leave_namespace();
+                }
+            }
+            all_resolved
+        });
+        if !resolved {
+            return Ty::Invalid;
+        }
+        // Macros may only run on code that has been checked successfully. 
+        // The code they generate may contain more macros to run
+        for i in 0.. {
+            if i > MAX_EXPANSION_DEPTH {
+                logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Maximum macro expansion depth reached ({MAX_EXPANSION_DEPTH})"),
+                    Span::builtin()
+                ));
+                return Ty::Invalid;
+            }
+            if logger.lock().unwrap().errors() > 0 {
+                break;
+            }
+            let generated = eval::run_macros(pool, logger.clone());
+            if generated.is_empty() {
+                break;
+            }
+            for (decl, code) in generated {
+                if !checker.check_generated(decl, &code, pool) {
+                    return Ty::Invalid;
+                }
+            }
+        }
+        Ty::Void
+    }
+    /// Add the code generated by the macros of `decl` next to it and check it
+    fn check_generated(&mut self, decl: DecoratedDecl, code: &Code, pool: &mut NodePool) -> bool {
+        // The code is added right after the declaration
+        let ArcSpan(src, range) = decl.get(pool).span_or_builtin(pool);
+        let at = ArcSpan(src, range.end..range.end);
+        let Some(generated) = codegen::instantiate(code, at, pool, self.logger.clone()) else {
+            return false;
+        };
+        let Some(context) = decl.get(pool).context() else {
+            return false;
+        };
+        decl.get_mut(pool).add_generated(generated);
+        let (name, span) = code.invocation.clone();
+        self.logger.lock().unwrap().push_note(format!("In code generated by macro {name} here"), span);
+        let resolved = self.resolve_passes(pool, |checker, pool| {
+            checker.with_context(&context, |checker| generated.try_resolve_ref(pool, checker).is_some())
+        });
+        self.logger.lock().unwrap().pop_note();
+        resolved
+    }
+    /// Run `pass` until it reports that everything has been resolved, 
+    /// logging why nodes could not be resolved if it gets stuck
+    fn resolve_passes(&mut self, pool: &NodePool, mut pass: impl FnMut(&mut Self, &NodePool) -> bool) -> bool {
         for i in 0.. {
             // todo: allow customizing max loop count via a compiler option
             if i > 1000 {
-                checker.logger.lock().unwrap().log(Message::new(
+                self.logger.lock().unwrap().log(Message::new(
                     Level::Error,
                     "Internal error: maximum check loop count reached (1000)",
                     Span::builtin()
@@ -290,38 +492,60 @@ impl Checker {
                     "Try simplifying your codebase, moving definitions of types \
                     and functions before their uses", true
                 )));
-                return Ty::Invalid;
+                return false;
             }
             // Reset node state marker
-            checker.some_nodes_resolve_state_changed = false;
-            if let Some(r) = ast.try_resolve_ref(pool, &mut checker) {
-                return r;
+            self.some_nodes_resolve_state_changed = false;
+            if pass(self, pool) {
+                return true;
             }
             // If no nodes' states changed, then we have ended up in an 
             // infinite unresolvable loop
-            if !checker.some_nodes_resolve_state_changed {
-                pool.release_unresolved(&checker, logger);
-                return Ty::Invalid;
+            if !self.some_nodes_resolve_state_changed {
+                pool.release_unresolved(self, self.logger.clone());
+                return false;
             }
         }
         unreachable!()
     }
+    /// Where the node being resolved is
+    pub fn context(&self) -> Context {
+        Context {
+            scope: self.current_scope,
+            namespace_stack: self.namespace_stack.clone(),
+            struct_body: self.struct_body.clone(),
+        }
+    }
+    /// Run `f` as if resolving a node at `context`
+    pub fn with_context<R>(&mut self, context: &Context, f: impl FnOnce(&mut Self) -> R) -> R {
+        let scope = std::mem::replace(&mut self.current_scope, context.scope);
+        let stack = std::mem::replace(&mut self.namespace_stack, context.namespace_stack.clone());
+        let body = std::mem::replace(&mut self.struct_body, context.struct_body.clone());
+        let res = f(self);
+        self.current_scope = scope;
+        self.namespace_stack = stack;
+        self.struct_body = body;
+        res
+    }
 
-    pub fn scopes(&self) -> ScopeIter {
+    pub fn scopes(&self) -> ScopeIter<'_> {
         ScopeIter::new(self.current_scope, &self.scopes, &self.namespace_stack)
     }
-    pub fn scope(&mut self) -> ScopeWithStackMut {
+    pub fn scope(&mut self) -> ScopeWithStackMut<'_> {
         ScopeWithStackMut {
             scope: self.scopes.get_mut(self.current_scope.0).unwrap(),
             stack: &self.namespace_stack
         }
     }
     pub fn enter_scope(&mut self, scope: &mut Option<ScopeID>) -> LeaveScope {
+        self.enter_scope_of_kind(scope, ScopeKind::Block)
+    }
+    pub fn enter_scope_of_kind(&mut self, scope: &mut Option<ScopeID>, kind: ScopeKind) -> LeaveScope {
         match scope {
             Some(scope) => self.current_scope = *scope,
             None => {
                 *scope = Some(ScopeID(self.scopes.len()));
-                self.scopes.push(Scope::new(self.current_scope));
+                self.scopes.push(Scope::new(self.current_scope, kind));
                 self.current_scope = scope.unwrap();
             }
         }
@@ -329,7 +553,6 @@ impl Checker {
     }
     fn leave_scope(&mut self) {
         if let Some(parent) = self.scope().scope.parent {
-            self.scope().scope.drop_ephemeral();
             self.current_scope = parent;
         }
     }
@@ -340,25 +563,447 @@ impl Checker {
     pub fn leave_namespace(&mut self) {
         self.namespace_stack.pop();
     }
+    /// Run `f` inside the body of the struct `ty` declared as `path`. 
+    /// Declarations in the body are placed in the namespace of the struct
+    pub fn with_struct_body<R>(&mut self, ty: Ty, path: &IdentPath, f: impl FnOnce(&mut Self) -> R) -> R {
+        let inner = self.namespace_stack.join(path);
+        let prev_ns = std::mem::replace(&mut self.namespace_stack, inner);
+        let prev_ty = self.struct_body.replace(ty);
+        let res = f(self);
+        self.namespace_stack = prev_ns;
+        self.struct_body = prev_ty;
+        res
+    }
+    /// The struct whose body is currently being resolved, if any
+    pub fn struct_body(&self) -> Option<&Ty> {
+        self.struct_body.as_ref()
+    }
+
+    /// Record a use of an entity found in the scope `found_in` from the 
+    /// current scope. If the entity is a local of some function, every 
+    /// closure between the use and the declaration captures it
+    pub fn capture(&mut self, found_in: ScopeID, name: &IdentPath, ent: &Entity) {
+        // Entities outside of functions are globals and need no capturing
+        let mut is_local = false;
+        let mut cur = Some(found_in);
+        while let Some(id) = cur {
+            let scope = &self.scopes[id.0];
+            if matches!(scope.kind, ScopeKind::Function | ScopeKind::Closure) {
+                is_local = true;
+                break;
+            }
+            cur = scope.parent;
+        }
+        if !is_local {
+            return;
+        }
+        let mut cur = Some(self.current_scope);
+        while let Some(id) = cur.filter(|id| *id != found_in) {
+            let scope = &mut self.scopes[id.0];
+            if scope.kind == ScopeKind::Closure && !scope.captures.iter().any(|c| c.name == name.to_string()) {
+                scope.captures.push(Capture {
+                    name: name.to_string(),
+                    ty: ent.ty(),
+                    decl_span: ent.span(),
+                });
+            }
+            cur = scope.parent;
+        }
+    }
+    /// Whether the current scope is inside a loop that `break` and `continue` 
+    /// can exit. Loops outside of the current function or closure don't count
+    pub fn in_loop(&self) -> bool {
+        let mut cur = Some(self.current_scope);
+        while let Some(id) = cur {
+            let scope = &self.scopes[id.0];
+            match scope.kind {
+                ScopeKind::Loop => return true,
+                ScopeKind::Function | ScopeKind::Closure => return false,
+                ScopeKind::Block => cur = scope.parent,
+            }
+        }
+        false
+    }
+    /// Get the entities captured by a closure scope, with the types decided 
+    /// for the variables that were declared without one
+    pub fn captures_of(&self, scope: ScopeID) -> Vec<Capture> {
+        self.scopes[scope.0].captures.iter()
+            .map(|c| Capture {
+                ty: self.decided_ty(&c.ty).unwrap_or_else(|| c.ty.clone()),
+                ..c.clone()
+            })
+            .collect()
+    }
+    /// The innermost function or closure the current scope is in
+    fn function_scope(&self) -> Option<ScopeID> {
+        let mut cur = Some(self.current_scope);
+        while let Some(id) = cur {
+            let scope = &self.scopes[id.0];
+            match scope.kind {
+                ScopeKind::Function | ScopeKind::Closure => return Some(id),
+                ScopeKind::Block | ScopeKind::Loop => cur = scope.parent,
+            }
+        }
+        None
+    }
+    /// Set the declared return type of the function whose body is the 
+    /// current scope, or `None` to have it inferred from what it returns
+    pub fn set_return_ty(&mut self, ty: Option<Ty>) {
+        self.scopes[self.current_scope.0].ret_ty = ty;
+    }
+    /// The declared return type of the enclosing function. This is 
+    /// `Some(None)` if the return type is inferred, and `None` outside of 
+    /// functions
+    pub fn return_ty(&self) -> Option<Option<Ty>> {
+        self.function_scope().map(|id| self.scopes[id.0].ret_ty.clone())
+    }
+    /// Record a value of type `ty` being returned at `span` from the 
+    /// enclosing function
+    pub fn push_return(&mut self, ty: Ty, span: ArcSpan) {
+        if let Some(id) = self.function_scope() {
+            self.scopes[id.0].returns.push((ty, span));
+        }
+    }
+    /// Record that the function `name` declared at `span` has its return 
+    /// type inferred, so it can't be used until its body has been checked
+    pub fn add_inferred_fun(&mut self, name: IdentPath, span: ArcSpan) {
+        self.inferred_funs.push((name, span));
+    }
+    /// Whether `name` used at `span` refers to a function with an inferred 
+    /// return type from inside of its own body
+    pub fn is_recursive_use(&self, name: &IdentPath, span: &ArcSpan) -> bool {
+        let Some((_, last)) = name.split_last() else {
+            return false;
+        };
+        self.inferred_funs.iter().any(|(fun, decl)| {
+            fun.split_last().is_some_and(|f| f.1 == last) &&
+                decl.0 == span.0 && decl.1.start <= span.1.start && span.1.end <= decl.1.end
+        })
+    }
+    /// Get the values returned from a function scope
+    pub fn returns_of(&self, scope: ScopeID) -> Vec<(Ty, ArcSpan)> {
+        self.scopes[scope.0].returns.clone()
+    }
+
+    /// Resolve something with `ty` as the type its value is expected to be. 
+    /// This is used for inferring types from context, such as the parameters 
+    /// of closures
+    pub fn with_expected_ty<R>(&mut self, ty: Option<Ty>, f: impl FnOnce(&mut Self) -> R) -> R {
+        let prev = std::mem::replace(&mut self.expected_ty, ty);
+        let res = f(self);
+        self.expected_ty = prev;
+        res
+    }
+    pub fn expected_ty(&self) -> Option<Ty> {
+        self.expected_ty.clone()
+    }
+    /// Run `f` with the declarations it resolves being marked as `extern`
+    pub fn with_extern<R>(&mut self, is_extern: bool, f: impl FnOnce(&mut Self) -> R) -> R {
+        let prev = std::mem::replace(&mut self.in_extern, is_extern);
+        let res = f(self);
+        self.in_extern = prev;
+        res
+    }
+    /// Check whether the declaration being resolved is `extern`. This 
+    /// clears the marker so that declarations nested inside it aren't
+    pub fn take_extern(&mut self) -> bool {
+        std::mem::take(&mut self.in_extern)
+    }
+    /// Run `f` with the variable it resolves being the target of an 
+    /// assignment
+    pub fn with_assignment<R>(&mut self, in_assignment: bool, f: impl FnOnce(&mut Self) -> R) -> R {
+        let prev = std::mem::replace(&mut self.in_assignment, in_assignment);
+        let res = f(self);
+        self.in_assignment = prev;
+        res
+    }
+    pub fn in_assignment(&self) -> bool {
+        self.in_assignment
+    }
+    /// Run `f` with the accessors it resolves belonging to a property of 
+    /// type `ty` on the struct `this`
+    pub fn with_property<R>(&mut self, this: Ty, ty: Ty, f: impl FnOnce(&mut Self) -> R) -> R {
+        let prev = self.property.replace((this, ty));
+        let res = f(self);
+        self.property = prev;
+        res
+    }
+    pub fn property(&self) -> Option<(Ty, Ty)> {
+        self.property.clone()
+    }
 
     pub fn mark_some_nodes_resolve_state_changed(&mut self) {
         self.some_nodes_resolve_state_changed = true;
     }
 
+    /// Find the type of the member `name` on a value of type `ty`. Members 
+    /// are either fields, or methods declared as `Type::name` whose first 
+    /// parameter is `this`
+    pub fn find_member(&self, ty: &Ty, name: &str) -> Option<Ty> {
+        if let Some(field) = self.find_field(ty, name) {
+            return Some(field.ty);
+        }
+        match self.find_method(ty, name)?.ty() {
+            Ty::Function { params, ret_ty } => Some(Ty::Function { params: params[1..].to_vec(), ret_ty }),
+            // Type parameters of generic methods are inferred from `this` 
+            // first, and the remaining ones where the method is called
+            Ty::Generic { params: generics, ty: fun } => {
+                let Ty::Function { params, ret_ty } = *fun else {
+                    return None;
+                };
+                let mut inferred = Vec::new();
+                params.first()?.1.infer(ty, &generics, &mut inferred);
+                let method = Ty::Function { params: params[1..].to_vec(), ret_ty }.substitute(&inferred);
+                let remaining = generics.into_iter()
+                    .filter(|g| !inferred.iter().any(|i| i.0 == *g))
+                    .collect::<Vec<_>>();
+                Some(if remaining.is_empty() {
+                    method
+                }
+                else {
+                    Ty::Generic { params: remaining, ty: method.into() }
+                })
+            }
+            _ => None,
+        }
+    }
+    /// Find the field `name` of a value of type `ty`, which may be a computed 
+    /// property
+    pub fn find_field(&self, ty: &Ty, name: &str) -> Option<Field> {
+        match ty.reduce() {
+            Ty::Struct { name: _, fields, decl_span: _, args: _ } => fields.iter().find(|f| f.name == name).cloned(),
+            _ => None,
+        }
+    }
+    /// Find the method `name` declared as `Type::name` on a value of type 
+    /// `ty`
+    pub fn find_method(&self, ty: &Ty, name: &str) -> Option<Entity> {
+        // The methods of lists are built into the compiler
+        if let Ty::List { ty: item } = ty.reduce() {
+            return list::list_method(item, name);
+        }
+        let path = IdentPath::parse(&ty.method_namespace()).join(Ident::from(name));
+        for scope in self.scopes() {
+            if let Some(ent) = scope.entities().find(&path) {
+                if let Ty::Function { params, ret_ty: _ } = ent.ty().ungeneric() {
+                    if params.first().is_some_and(|p| p.0.as_deref() == Some("this")) {
+                        return Some(ent.clone());
+                    }
+                }
+            }
+        }
+        None
+    }
+    /// Get the names of all the members available on a value of type `ty`
+    pub fn members_of(&self, ty: &Ty) -> Vec<String> {
+        let mut res = Vec::new();
+        if let Ty::Struct { name: _, fields, decl_span: _, args: _ } = ty.reduce() {
+            res.extend(fields.iter().map(|f| f.name.clone()));
+        }
+        if let Ty::List { ty: _ } = ty.reduce() {
+            res.extend(list::METHODS.iter().map(|m| m.to_string()));
+        }
+        let path = IdentPath::parse(&ty.method_namespace());
+        let mut methods = Vec::new();
+        for scope in self.scopes() {
+            for (name, ent) in scope.entities().find_members(&path) {
+                let is_method = matches!(
+                    ent.ty().ungeneric(), Ty::Function { params, ret_ty: _ }
+                    if params.first().is_some_and(|p| p.0.as_deref() == Some("this"))
+                );
+                if is_method && !methods.contains(&name.to_string()) {
+                    methods.push(name.to_string());
+                }
+            }
+        }
+        methods.sort();
+        res.extend(methods);
+        res
+    }
+
+    /// Get the type of the items produced by iterating a value of type `ty` 
+    /// with `for`, or `None` if it's not iterable. Strings iterate their 
+    /// characters, lists their items and maps their entries, iterators are 
+    /// types with a `next(this) -> Item?` method, and other types are 
+    /// iterable if they have an `iter(this)` method that returns an iterator
+    pub fn iter_item_ty(&self, ty: &Ty) -> Option<Ty> {
+        match ty.reduce() {
+            Ty::String => return Some(Ty::String),
+            Ty::List { ty } => return Some(*ty.clone()),
+            Ty::Map { key, value } => return Some(Ty::new_map_entry(*key.clone(), *value.clone())),
+            other if other.is_unreal() => return Some(Ty::Invalid),
+            _ => {}
+        }
+        if let Some(Ty::Function { params, ret_ty }) = self.find_member(ty, "next") {
+            if let (true, Ty::Option { ty }) = (params.is_empty(), ret_ty.reduce()) {
+                return Some(*ty.clone());
+            }
+        }
+        if let Some(Ty::Function { params, ret_ty }) = self.find_member(ty, "iter") {
+            // Avoid recursing forever on types whose `iter` returns themselves
+            if params.is_empty() && *ret_ty != *ty {
+                return self.iter_item_ty(&ret_ty);
+            }
+        }
+        None
+    }
+
+    /// Whether a value of type `ty` can be converted to a string, for 
+    /// example in the holes of interpolated strings. Besides the builtin 
+    /// types, this is true for types with a `toString(this) -> string` method
+    pub fn is_string_convertible(&self, ty: &Ty) -> bool {
+        match ty.reduce() {
+            Ty::String | Ty::Int | Ty::Float | Ty::Bool => true,
+            Ty::List { ty } => self.is_string_convertible(ty),
+            Ty::Map { key, value } => self.is_string_convertible(key) && self.is_string_convertible(value),
+            other if other.is_unreal() => true,
+            other => matches!(
+                self.find_member(other, "toString"),
+                Some(Ty::Function { params, ret_ty }) if params.is_empty() && *ret_ty == Ty::String
+            ),
+        }
+    }
+
     pub fn expect_ty_decided(&self, a: Ty, span: Option<ArcSpan>) -> bool {
         if let Ty::Undecided(name, a_span) = a {
             self.logger.lock().unwrap().log(Message::new(
                 Level::Error,
                 format!("The type of {name} needs to be known at this point"),
-                span.unwrap_or(ArcSpan::builtin()).as_ref()
+                span.unwrap_or_default().as_ref()
             ).note(Note::new_at(
                 format!("Declaration of {name} here"),
                 a_span.as_ref()
+            )).note(Note::new(
+                format!("Assign a value to {name} before this, or give it a type when declaring it"),
+                true
             )));
             return false;
         }
         true
     }
+    /// The type that has been decided for `ty` if it is undecided, or `ty` 
+    /// itself otherwise
+    pub fn decided_ty(&self, ty: &Ty) -> Option<Ty> {
+        match ty {
+            Ty::Undecided(_, decl_span) => self.decided.get(decl_span).map(|d| d.0.clone()),
+            other => Some(other.clone()),
+        }
+    }
+    /// Unify the undecided type `var` of a variable with the type `ty` of a 
+    /// value assigned to it at `span`. The first assignment decides the type 
+    /// of the variable, and the later ones are checked against it
+    pub fn decide_ty(&mut self, var: &Ty, ty: Ty, span: ArcSpan) {
+        let Ty::Undecided(name, decl_span) = var else {
+            return;
+        };
+        if let Some((decided, decided_span)) = self.decided.get(decl_span) {
+            if !ty.convertible(decided) {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot convert from type {ty} to {decided}"),
+                    span.as_ref()
+                ).note(Note::new_at(
+                    format!("The type of {name} was decided to be {decided} here"),
+                    decided_span.as_ref()
+                )).note(Note::new_at(
+                    format!("Declaration of {name} here"),
+                    decl_span.as_ref()
+                )));
+            }
+            return;
+        }
+        // A branch that never finishes doesn't give the variable a value
+        if ty.is_never() {
+            return;
+        }
+        let ty = if ty.is_partial() {
+            self.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Cannot decide the type of {name} from a value of type {ty}"),
+                span.as_ref()
+            ).note(Note::new_at(
+                format!("Declaration of {name} here"),
+                decl_span.as_ref()
+            )).note(Note::new(format!("Give {name} a type when declaring it"), true)));
+            Ty::Invalid
+        }
+        else {
+            ty
+        };
+        self.decided.insert(decl_span.clone(), (ty, span));
+        self.mark_some_nodes_resolve_state_changed();
+    }
+    /// Check that `ty` can be used as the key of a map
+    pub fn expect_ty_hashable(&self, ty: &Ty, span: Option<ArcSpan>) -> bool {
+        if !ty.is_hashable() {
+            self.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!("Type {ty} can not be used as a map key"),
+                span.unwrap_or_default().as_ref()
+            ).note(Note::new("Map keys must be of type int, string, bool or an enum", false)));
+            return false;
+        }
+        true
+    }
+    /// Give the generic type `ty` the types that have been inferred for its 
+    /// parameters, logging an error if some of them couldn't be inferred
+    pub fn instantiate_inferred(&self, ty: &Ty, inferred: &[(Ty, Ty)], span: Option<ArcSpan>) -> Ty {
+        let Ty::Generic { params, ty: _ } = ty else {
+            return ty.clone();
+        };
+        let args = params.iter()
+            .map(|p| inferred.iter().find(|i| i.0 == *p).map(|i| i.1.clone()))
+            .collect::<Vec<_>>();
+        let missing = params.iter().zip(&args)
+            .filter(|(_, a)| a.is_none())
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        if let Some(first) = missing.first() {
+            let first_span = first.span();
+            self.logger.lock().unwrap().log(Message::new(
+                Level::Error,
+                format!(
+                    "Cannot infer the type parameter{} {}",
+                    if missing.len() == 1 { "" } else { "s" },
+                    missing.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+                ),
+                span.unwrap_or_default().as_ref()
+            ).note(Note::new_at(
+                format!("Type parameter {first} declared here"),
+                first_span.as_ref()
+            )).note(Note::new("Give the type arguments explicitly, like 'name::<int>'", true)));
+            return Ty::Invalid;
+        }
+        ty.instantiate(&args.into_iter().flatten().collect::<Vec<_>>())
+    }
+    /// Infer the return type of a function from the values it returns. The 
+    /// first one with a known type decides the return type, and the rest are 
+    /// checked against it
+    pub fn infer_return_ty(&self, returns: &[(Ty, ArcSpan)]) -> Ty {
+        // A function that never finishes never returns anything
+        if returns.iter().all(|r| r.0.is_never()) {
+            return Ty::Never;
+        }
+        let Some((ty, decided_span)) = returns.iter()
+            .find(|r| !r.0.is_unreal() && !r.0.is_partial())
+            .or_else(|| returns.iter().find(|r| !r.0.is_unreal()))
+        else {
+            return Ty::Invalid;
+        };
+        for (other, span) in returns {
+            if !other.convertible(ty) {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    format!("Cannot convert from type {other} to {ty}"),
+                    span.as_ref()
+                ).note(Note::new_at(
+                    format!("The return type was decided to be {ty} here"),
+                    decided_span.as_ref()
+                )).note(Note::new("Give the function a return type, like '-> Type'", true)));
+            }
+        }
+        ty.clone()
+    }
     pub fn expect_ty_eq(&self, a: Ty, b: Ty, span: Option<ArcSpan>) -> Ty {
         if self.expect_ty_decided(a.clone(), span.clone()) &&
             self.expect_ty_decided(b.clone(), span.clone()) {
@@ -366,7 +1011,7 @@ impl Checker {
                 self.logger.lock().unwrap().log(Message::new(
                     Level::Error,
                     format!("Cannot convert from type {b} to {a}"),
-                    span.unwrap_or(ArcSpan::builtin()).as_ref()
+                    span.unwrap_or_default().as_ref()
                 ));
             }
             a.or(b)
@@ -375,6 +1020,32 @@ impl Checker {
             Ty::Invalid
         }
     }
+    /// Record the full type of a struct
+    pub fn define_struct(&mut self, ty: Ty, is_extern: bool) {
+        if is_extern {
+            self.extern_structs.insert(ty.span());
+        }
+        self.structs.insert(ty.span(), ty);
+    }
+    /// Check whether `ty` is a struct provided by the host
+    pub fn is_extern_struct(&self, ty: &Ty) -> bool {
+        matches!(ty.reduce(), Ty::Struct { name: _, fields: _, decl_span, args: _ } if self.extern_structs.contains(decl_span))
+    }
+    /// Get the full type of `ty` if it is a forward declared struct, or 
+    /// `ty` itself otherwise
+    pub fn complete_struct(&self, ty: &Ty) -> Ty {
+        match ty.reduce() {
+            Ty::Struct { name: _, fields: _, decl_span, args } => match self.structs.get(decl_span) {
+                Some(full) => full.instantiate(args),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+    /// Check whether `ty` is a struct whose fields haven't been resolved yet
+    pub fn is_forward_declared(&self, ty: &Ty) -> bool {
+        matches!(ty.reduce(), Ty::Struct { name: _, fields: _, decl_span, args: _ } if !self.structs.contains_key(decl_span))
+    }
     
     pub fn logger(&self) -> LoggerRef {
         self.logger.clone()
@@ -3,18 +3,72 @@ use crate::shared::src::ArcSpan;
 
 use super::ty::Ty;
 
-#[derive(Debug)]
+/// A local variable of an enclosing function that is used inside a closure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub name: String,
+    pub ty: Ty,
+    pub decl_span: ArcSpan,
+}
+
+#[derive(Debug, Clone)]
 pub struct Entity {
     /// The type of the entity
     ty: Ty,
     decl_span: ArcSpan,
     /// Whether this entity only exists after declaration, i.e. variables
     ephemeral: bool,
+    /// Whether this entity is provided by the host instead of being 
+    /// compiled, i.e. `extern fun`
+    external: bool,
+    /// Whether this entity is a `macro`, which can only be run at compile 
+    /// time
+    is_macro: bool,
+    /// The name of the compiler function that implements this entity, for 
+    /// builtins such as `Reflect::error`
+    intrinsic: Option<&'static str>,
+    /// The names of the parameters of this function that have default values 
+    /// and may be left out of calls
+    optional_params: Vec<String>,
+    /// Whether this entity can't be assigned a new value, i.e. parameters 
+    /// that aren't declared `var`
+    read_only: bool,
+    /// Whether this entity is a binary operator that also implements the 
+    /// operation with its operands swapped
+    commutative: bool,
 }
 
 impl Entity {
     pub fn new(ty: Ty, decl_span: ArcSpan, ephemeral: bool) -> Self {
-        Self { ty, decl_span, ephemeral }
+        Self {
+            ty, decl_span, ephemeral,
+            external: false, is_macro: false, intrinsic: None, optional_params: Vec::new(),
+            read_only: false, commutative: false,
+        }
+    }
+    pub fn with_external(mut self, external: bool) -> Self {
+        self.external = external;
+        self
+    }
+    pub fn with_macro(mut self, is_macro: bool) -> Self {
+        self.is_macro = is_macro;
+        self
+    }
+    pub fn with_intrinsic(mut self, intrinsic: &'static str) -> Self {
+        self.intrinsic = Some(intrinsic);
+        self
+    }
+    pub fn with_optional_params(mut self, optional_params: Vec<String>) -> Self {
+        self.optional_params = optional_params;
+        self
+    }
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    pub fn with_commutative(mut self, commutative: bool) -> Self {
+        self.commutative = commutative;
+        self
     }
     pub fn span(&self) -> ArcSpan {
         self.decl_span.clone()
@@ -25,4 +79,27 @@ impl Entity {
     pub fn ephemeral(&self) -> bool {
         self.ephemeral
     }
+    pub fn external(&self) -> bool {
+        self.external
+    }
+    pub fn is_macro(&self) -> bool {
+        self.is_macro
+    }
+    pub fn intrinsic(&self) -> Option<&'static str> {
+        self.intrinsic
+    }
+    pub fn optional_params(&self) -> &[String] {
+        &self.optional_params
+    }
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+    pub fn commutative(&self) -> bool {
+        self.commutative
+    }
+    /// Whether this entity can be referred to from a position in the source. 
+    /// Ephemeral entities are only visible after their declaration
+    pub fn visible_at(&self, span: &ArcSpan) -> bool {
+        !self.ephemeral || self.decl_span.0 != span.0 || self.decl_span.1.end <= span.1.start
+    }
 }
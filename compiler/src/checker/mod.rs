@@ -1,27 +1,11 @@
 
-use std::fmt::Display;
-
 pub mod path;
 pub mod ty;
 pub mod pool;
 pub mod resolve;
 pub mod entity;
 pub mod coherency;
-
-pub(crate) trait Ice: Sized {
-    type R;
-    fn ice(self, msg: &str) -> Self::R;
-    fn ice_due_to<D: Display>(self, msg: &str, cause: D) -> Self::R {
-        self.ice(&format!("{msg}; cause: {cause}"))
-    }
-}
-
-impl<T> Ice for Option<T> {
-    type R = T;
-    fn ice(self, msg: &str) -> Self::R {
-        self.unwrap_or_else(|| panic!("Internal compiler error: {msg}"))
-    }
-}
+pub mod operators;
 
 #[macro_export]
 macro_rules! ice {
@@ -29,3 +13,46 @@ macro_rules! ice {
         panic!("Internal compiler error: {}", format!($msg $($rest)*))
     };
 }
+
+/// Parse and check `code` as the only source of a project, returning the 
+/// text of every error logged
+#[cfg(test)]
+pub(crate) fn check_errors(code: &str) -> Vec<String> {
+    check_errors_with_notes(code).into_iter().map(|e| e[0].clone()).collect()
+}
+
+/// Like `check_errors`, but with the text of each error followed by the 
+/// text of its notes
+#[cfg(test)]
+pub(crate) fn check_errors_with_notes(code: &str) -> Vec<Vec<String>> {
+    check_messages(code, crate::shared::logger::Level::Error)
+}
+
+/// Like `check_errors`, but returns the text of every warning logged
+#[cfg(test)]
+pub(crate) fn check_warnings(code: &str) -> Vec<String> {
+    check_messages(code, crate::shared::logger::Level::Warning).into_iter().map(|e| e[0].clone()).collect()
+}
+
+#[cfg(test)]
+fn check_messages(code: &str, level: crate::shared::logger::Level) -> Vec<Vec<String>> {
+    use std::sync::{Arc, Mutex};
+    use crate::shared::{logger::Logger, src::{Src, SrcPool}};
+    use crate::parser::parse::NodePool;
+
+    let errors = Arc::new(Mutex::new(vec![]));
+    let logger = {
+        let errors = errors.clone();
+        Logger::new(move |msg| if msg.level == level {
+            errors.lock().unwrap().push(
+                std::iter::once(msg.info()).chain(msg.notes()).map(String::from).collect()
+            );
+        })
+    };
+    let src_pool = SrcPool::from_srcs("".into(), vec![Src::from_memory("test.dash", code)]);
+    let mut node_pool = NodePool::new();
+    let ast_pool = pool::ASTPool::parse_src_pool(&mut node_pool, &src_pool, logger.clone());
+    coherency::Checker::try_resolve(&ast_pool, &mut node_pool, logger);
+    let res = errors.lock().unwrap().clone();
+    res
+}
@@ -0,0 +1,77 @@
+use crate::{ast::token::op::{BinaryOp, UnaryOp}, ice};
+
+use super::{coherency::Checker, entity::Entity, path::{Ident, IdentPath}, ty::Ty};
+
+/// How a binary operation like `a + b` was resolved to the function that 
+/// implements it
+#[derive(Debug, Clone)]
+pub struct BinOpResolution {
+    /// The operator function
+    pub entity: Entity,
+    /// The operator the function implements, which is not the one written 
+    /// for derived operators like `!=`
+    pub op: BinaryOp,
+    /// Whether the operands are passed to the function in reverse order, 
+    /// for commutative operators
+    pub swapped: bool,
+    /// Whether the result of the function is negated, for derived operators
+    pub negated: bool,
+}
+
+impl BinOpResolution {
+    pub fn ret_ty(&self) -> Ty {
+        match self.entity.ty() {
+            Ty::Function { params: _, ret_ty } => *ret_ty,
+            other => ice!(
+                "encountered entity for binop '{}' that wasn't a function type, but {other}",
+                self.op
+            ),
+        }
+    }
+}
+
+fn find_entity(checker: &Checker, ident: Ident) -> Option<Entity> {
+    let name = IdentPath::new([ident], false);
+    checker.scopes().find_map(|s| s.entities().find(&name).cloned())
+}
+
+/// Find the function declared for `a op b`, or for `b op a` if it's 
+/// commutative
+pub fn find_binop(checker: &Checker, a: &Ty, op: BinaryOp, b: &Ty) -> Option<BinOpResolution> {
+    if let Some(entity) = find_entity(checker, Ident::BinOp(a.clone(), op, b.clone())) {
+        return Some(BinOpResolution { entity, op, swapped: false, negated: false });
+    }
+    find_entity(checker, Ident::BinOp(b.clone(), op, a.clone()))
+        .filter(|e| e.commutative())
+        .map(|entity| BinOpResolution { entity, op, swapped: true, negated: false })
+}
+
+/// Resolve `a op b`, deriving operators like `!=` from their counterparts if 
+/// they haven't been declared
+pub fn resolve_binop(checker: &Checker, a: &Ty, op: BinaryOp, b: &Ty) -> Option<BinOpResolution> {
+    find_binop(checker, a, op, b).or_else(|| {
+        let base = find_binop(checker, a, op.derived_from()?, b)?;
+        (*base.ret_ty().reduce() == Ty::Bool).then_some(BinOpResolution { negated: true, ..base })
+    })
+}
+
+/// Resolve `op a` to the function that implements it
+pub fn resolve_unop(checker: &Checker, op: UnaryOp, a: &Ty) -> Option<Entity> {
+    find_entity(checker, Ident::UnOp(op, a.clone()))
+}
+
+/// Resolve `a[b]` to a user-defined indexing function
+pub fn resolve_index(checker: &Checker, a: &Ty, b: &Ty) -> Option<Entity> {
+    find_entity(checker, Ident::Index(a.clone(), b.clone()))
+}
+
+/// Find an existing operator that a new declaration with the name `ident` 
+/// would conflict with
+pub fn find_conflict(checker: &Checker, ident: &Ident, commutative: bool) -> Option<Entity> {
+    match ident {
+        Ident::BinOp(a, op, b) => find_binop(checker, a, *op, b)
+            .or_else(|| commutative.then(|| find_binop(checker, b, *op, a)).flatten())
+            .map(|r| r.entity),
+        other => find_entity(checker, other.clone()),
+    }
+}
@@ -11,6 +11,12 @@ pub enum Ident {
     Decorator(String),
     UnOp(op::UnaryOp, Ty),
     BinOp(Ty, op::BinaryOp, Ty),
+    /// A user-defined conversion from the first type to the second, used by 
+    /// `value as Type`
+    Conversion(Ty, Ty),
+    /// A user-defined indexing operator for values of the first type with 
+    /// indices of the second, used by `value[index]`
+    Index(Ty, Ty),
 }
 
 impl From<&str> for Ident {
@@ -37,6 +43,8 @@ impl Display for Ident {
             Self::Decorator(name) => write!(f, "@{name}"),
             Self::UnOp(op, t) => write!(f, "unop`{op}{t}`"),
             Self::BinOp(a, op, b) => write!(f, "binop`{a}{op}{b}`"),
+            Self::Conversion(a, b) => write!(f, "conv`{a} as {b}`"),
+            Self::Index(a, b) => write!(f, "index`{a}[{b}]`"),
         }
     }
 }
@@ -51,23 +59,34 @@ impl IdentPath {
     pub fn new<T: Into<Vec<Ident>>>(path: T, absolute: bool) -> Self {
         Self { components: path.into(), absolute }
     }
-    // pub fn parse(mut value: &str) -> Self {
-    //     let absolute;
-    //     if let Some(relative) = value.strip_prefix("::") {
-    //         absolute = true;
-    //         value = relative;
-    //     }
-    //     else {
-    //         absolute = false;
-    //     }
-    //     Self {
-    //         components: value.split("::").map(Ident::from).collect(),
-    //         absolute
-    //     }
-    // }
+    pub fn parse(mut value: &str) -> Self {
+        let absolute;
+        if let Some(relative) = value.strip_prefix("::") {
+            absolute = true;
+            value = relative;
+        }
+        else {
+            absolute = false;
+        }
+        Self {
+            components: value.split("::").map(Ident::from).collect(),
+            absolute
+        }
+    }
     pub fn to_full(&self) -> FullIdentPath {
         FullIdentPath::new(self.components.clone())
     }
+    /// Add a component to the end of this path
+    pub fn join(&self, ident: Ident) -> IdentPath {
+        let mut new = self.clone();
+        new.components.push(ident);
+        new
+    }
+    /// Split this path into its parent path and last component
+    pub fn split_last(&self) -> Option<(IdentPath, &Ident)> {
+        let (last, parent) = self.components.split_last()?;
+        Some((IdentPath::new(parent, self.absolute), last))
+    }
     pub fn is_absolute(&self) -> bool {
         self.absolute
     }
@@ -116,6 +135,12 @@ impl FullIdentPath {
     pub fn is_empty(&self) -> bool {
         self.components.is_empty()
     }
+    /// If this path is an item directly inside the namespace `ns`, returns 
+    /// the name of the item
+    pub fn member_of(&self, ns: &FullIdentPath) -> Option<&Ident> {
+        let (last, parent) = self.components.split_last()?;
+        (parent == ns.components.as_slice()).then_some(last)
+    }
 }
 
 impl Display for FullIdentPath {
@@ -1,25 +1,42 @@
-
-use crate::ast::expr::ExprList;
+use crate::ast::expr::Module;
 use crate::parser::tokenizer::Tokenizer;
 use crate::shared::src::SrcPool;
 use crate::shared::logger::LoggerRef;
 use crate::parser::parse::{ParseRef, NodePool};
 
-pub type AST = ExprList;
+use super::path::Ident;
+
+/// A parsed source file along with the namespace its items live in
+pub struct AST {
+    namespace: Vec<Ident>,
+    module: Module,
+}
+
+impl AST {
+    pub fn namespace(&self) -> &[Ident] {
+        &self.namespace
+    }
+    pub fn module(&self) -> Module {
+        self.module
+    }
+}
 
 pub struct ASTPool {
     asts: Vec<AST>,
 }
 
-impl<'s: 'g, 'g> ASTPool {
+impl ASTPool {
     pub fn parse_src_pool(list: &mut NodePool, pool: &SrcPool, logger: LoggerRef) -> Self {
         Self {
             asts: pool.iter()
-                .filter_map(|src| ExprList::parse_complete(
-                    list,
-                    src.clone(),
-                    Tokenizer::new(&src, logger.clone())
-                ).ok())
+                .filter_map(|src| Some(AST {
+                    namespace: pool.module_path(&src).into_iter().map(Ident::from).collect(),
+                    module: Module::parse_complete(
+                        list,
+                        src.clone(),
+                        Tokenizer::new(&src, logger.clone())
+                    ).ok()?,
+                }))
                 .collect(),
         }
     }
@@ -44,4 +61,4 @@ impl<'a> IntoIterator for &'a mut ASTPool {
     fn into_iter(self) -> Self::IntoIter {
         self.asts.iter_mut()
     }
-}
\ No newline at end of file
+}
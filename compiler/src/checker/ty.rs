@@ -3,6 +3,39 @@ use std::fmt::Display;
 use crate::ice;
 use crate::shared::src::ArcSpan;
 
+/// A named field in a struct
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Field {
+    pub name: String,
+    pub ty: Ty,
+    /// Whether this field has a default value, in which case it may be 
+    /// omitted when constructing the struct
+    pub has_default: bool,
+    /// The accessors of a computed property, which has no storage of its 
+    /// own, or `None` for regular fields
+    pub accessors: Option<Accessors>,
+    /// The fields a computed property is derived from, so that it can be 
+    /// updated whenever they change
+    pub depends: Vec<String>,
+    pub decl_span: ArcSpan,
+}
+
+/// Which accessors a computed property has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Accessors {
+    pub get: bool,
+    pub set: bool,
+}
+
+/// A variant of an enum
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Variant {
+    pub name: String,
+    /// The fields of a struct-like variant, or `None` for unit variants
+    pub fields: Option<Vec<Field>>,
+    pub decl_span: ArcSpan,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Ty {
     /// The type of a variable whose real type has not yet been inferred
@@ -31,6 +64,15 @@ pub enum Ty {
     Option {
         ty: Box<Ty>,
     },
+    /// Growable list of values of the same type
+    List {
+        ty: Box<Ty>,
+    },
+    /// Associative container from hashable keys to values
+    Map {
+        key: Box<Ty>,
+        value: Box<Ty>,
+    },
     /// Alias for another type. Can be implicitly converted to the other type
     Alias {
         name: String,
@@ -44,6 +86,34 @@ pub enum Ty {
         ty: Box<Ty>,
         decl_span: ArcSpan,
     },
+    /// Structure type with named fields
+    Struct {
+        name: String,
+        fields: Vec<Field>,
+        decl_span: ArcSpan,
+        /// The type arguments of an instance of a generic struct, like the 
+        /// `int` of `Box<int>`
+        args: Vec<Ty>,
+    },
+    /// Enumeration type whose value is one of its variants
+    Enum {
+        name: String,
+        variants: Vec<Variant>,
+        decl_span: ArcSpan,
+    },
+    /// A type parameter of a generic function or struct. Inside the 
+    /// declaration it stands for any type, so it is only equal to itself
+    Param {
+        name: String,
+        decl_span: ArcSpan,
+    },
+    /// A generic function or struct, which becomes a concrete type once its 
+    /// type parameters are given types with `instantiate`
+    Generic {
+        /// The type parameters, which are all `Ty::Param`s
+        params: Vec<Ty>,
+        ty: Box<Ty>,
+    },
 }
 
 impl Ty {
@@ -59,6 +129,25 @@ impl Ty {
         }
     }
 
+    /// The type of the items produced by iterating a map with `for`, which 
+    /// have a `key` and a `value` field
+    pub fn new_map_entry(key: Ty, value: Ty) -> Self {
+        let field = |name: &str, ty| Field {
+            name: name.into(),
+            ty,
+            has_default: false,
+            accessors: None,
+            depends: vec![],
+            decl_span: ArcSpan::builtin(),
+        };
+        Self::Struct {
+            name: String::from("MapEntry"),
+            fields: vec![field("key", key), field("value", value)],
+            decl_span: ArcSpan::builtin(),
+            args: vec![],
+        }
+    }
+
     pub fn is_never(&self) -> bool {
         matches!(self, Ty::Never)
     }
@@ -72,6 +161,28 @@ impl Ty {
         matches!(self, Ty::Invalid | Ty::Never)
     }
 
+    /// Whether some part of this type is unreal, like `never?` which is the 
+    /// type of `none` when no other type is known
+    pub fn is_partial(&self) -> bool {
+        match self.reduce() {
+            Ty::Option { ty } | Ty::List { ty } => ty.is_unreal() || ty.is_partial(),
+            Ty::Map { key, value } => key.is_unreal() || value.is_unreal() ||
+                key.is_partial() || value.is_partial(),
+            _ => false,
+        }
+    }
+
+    /// Whether values of this type can be used as map keys
+    pub fn is_hashable(&self) -> bool {
+        // Type parameters can be used as keys so that generic code can work 
+        // with maps
+        self.is_unreal() || matches!(
+            self.reduce(),
+            Ty::Int | Ty::String | Ty::Bool | Ty::Enum { name: _, variants: _, decl_span: _ } |
+            Ty::Param { name: _, decl_span: _ }
+        )
+    }
+
     /// Reduce type into its canonical representation, for example remove aliases
     pub fn reduce(&self) -> &Ty {
         match self {
@@ -85,7 +196,40 @@ impl Ty {
     /// 
     /// In most cases this means equality
     pub fn convertible(&self, other: &Ty) -> bool {
-        self.is_unreal() || other.is_unreal() || *self.reduce() == *other.reduce()
+        if self.is_unreal() || other.is_unreal() {
+            return true;
+        }
+        match (self.reduce(), other.reduce()) {
+            // Parameter names don't matter for function values, and a function 
+            // that returns something can be used where nothing is returned
+            (
+                Ty::Function { params: a, ret_ty: a_ret },
+                Ty::Function { params: b, ret_ty: b_ret }
+            ) => {
+                a.len() == b.len() &&
+                    a.iter().zip(b).all(|(a, b)| a.1.reduce() == b.1.reduce()) &&
+                    (a_ret.convertible(b_ret) || **b_ret == Ty::Void)
+            }
+            // Structs are the same if they have the same declaration, since 
+            // fields that refer to their own struct only know its forward 
+            // declaration
+            (
+                Ty::Struct { name: a, fields: _, decl_span: a_span, args: a_args },
+                Ty::Struct { name: b, fields: _, decl_span: b_span, args: b_args }
+            ) => {
+                a == b && a_span == b_span && a_args.len() == b_args.len() &&
+                    a_args.iter().zip(b_args).all(|(a, b)| a.convertible(b) && b.convertible(a))
+            }
+            (Ty::Option { ty: a }, Ty::Option { ty: b }) => a.convertible(b),
+            // Any value can be implicitly made optional
+            (a, Ty::Option { ty: b }) => a.convertible(b),
+            (Ty::List { ty: a }, Ty::List { ty: b }) => a.convertible(b),
+            (
+                Ty::Map { key: a_key, value: a_value },
+                Ty::Map { key: b_key, value: b_value }
+            ) => a_key.convertible(b_key) && a_value.convertible(b_value),
+            (a, b) => a == b
+        }
     }
 
     pub fn span(&self) -> ArcSpan {
@@ -100,8 +244,162 @@ impl Ty {
             Ty::String => ArcSpan::builtin(),
             Ty::Function { params: _, ret_ty: _ } => ArcSpan::builtin(),
             Ty::Option { ty: _ } => ArcSpan::builtin(),
+            Ty::List { ty: _ } => ArcSpan::builtin(),
+            Ty::Map { key: _, value: _ } => ArcSpan::builtin(),
             Ty::Alias { name: _, ty: _, decl_span } |
-            Ty::Named { name: _, ty: _, decl_span } => decl_span.clone(),
+            Ty::Named { name: _, ty: _, decl_span } |
+            Ty::Struct { name: _, fields: _, decl_span, args: _ } |
+            Ty::Enum { name: _, variants: _, decl_span } |
+            Ty::Param { name: _, decl_span } => decl_span.clone(),
+            Ty::Generic { params: _, ty } => ty.span(),
+        }
+    }
+
+    /// Replace the type parameters in this type with the types they have 
+    /// been given in `args`. Parameters missing from `args` are kept
+    pub fn substitute(&self, args: &[(Ty, Ty)]) -> Ty {
+        let sub = |ty: &Ty| Box::new(ty.substitute(args));
+        let sub_fields = |fields: &[Field]| fields.iter()
+            .map(|f| Field { ty: f.ty.substitute(args), ..f.clone() })
+            .collect();
+        match self {
+            Ty::Param { name: _, decl_span: _ } => args.iter()
+                .find(|a| a.0 == *self)
+                .map(|a| a.1.clone())
+                .unwrap_or_else(|| self.clone()),
+            Ty::Function { params, ret_ty } => Ty::Function {
+                params: params.iter().map(|p| (p.0.clone(), p.1.substitute(args))).collect(),
+                ret_ty: sub(ret_ty),
+            },
+            Ty::Option { ty } => Ty::Option { ty: sub(ty) },
+            Ty::List { ty } => Ty::List { ty: sub(ty) },
+            Ty::Map { key, value } => Ty::Map { key: sub(key), value: sub(value) },
+            Ty::Alias { name, ty, decl_span } => Ty::Alias {
+                name: name.clone(), ty: sub(ty), decl_span: decl_span.clone()
+            },
+            Ty::Struct { name, fields, decl_span, args: ty_args } => Ty::Struct {
+                name: name.clone(),
+                fields: sub_fields(fields),
+                decl_span: decl_span.clone(),
+                args: ty_args.iter().map(|a| a.substitute(args)).collect(),
+            },
+            Ty::Enum { name, variants, decl_span } => Ty::Enum {
+                name: name.clone(),
+                variants: variants.iter()
+                    .map(|v| Variant { fields: v.fields.as_deref().map(sub_fields), ..v.clone() })
+                    .collect(),
+                decl_span: decl_span.clone(),
+            },
+            Ty::Generic { params, ty } => Ty::Generic {
+                params: params.clone(),
+                // Parameters of this generic shadow those being substituted
+                ty: ty.substitute(
+                    &args.iter().filter(|a| !params.contains(&a.0)).cloned().collect::<Vec<_>>()
+                ).into(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// Infer the types of the type parameters `params` by matching this type, 
+    /// which may contain them, against the type `actual` of a value that is 
+    /// used in its place. Parameters that have already been inferred are kept
+    pub fn infer(&self, actual: &Ty, params: &[Ty], inferred: &mut Vec<(Ty, Ty)>) {
+        if actual.is_unreal() || actual.is_undecided() {
+            return;
+        }
+        match (self, actual.reduce()) {
+            (Ty::Param { name: _, decl_span: _ }, _) if params.contains(self) && self != actual &&
+                !inferred.iter().any(|i| i.0 == *self) => {
+                inferred.push((self.clone(), actual.clone()));
+            }
+            (
+                Ty::Function { params: a, ret_ty: a_ret },
+                Ty::Function { params: b, ret_ty: b_ret }
+            ) => {
+                for (a, b) in a.iter().zip(b) {
+                    a.1.infer(&b.1, params, inferred);
+                }
+                a_ret.infer(b_ret, params, inferred);
+            }
+            (Ty::Option { ty: a }, Ty::Option { ty: b }) => a.infer(b, params, inferred),
+            // Values are implicitly made optional
+            (Ty::Option { ty: a }, b) => a.infer(b, params, inferred),
+            (Ty::List { ty: a }, Ty::List { ty: b }) => a.infer(b, params, inferred),
+            (
+                Ty::Map { key: a_key, value: a_value },
+                Ty::Map { key: b_key, value: b_value }
+            ) => {
+                a_key.infer(b_key, params, inferred);
+                a_value.infer(b_value, params, inferred);
+            }
+            // Instances of the same generic struct have the same fields. 
+            // Forward declarations have no fields, only arguments
+            (
+                Ty::Struct { name: _, fields: a, decl_span: a_span, args: a_args },
+                Ty::Struct { name: _, fields: b, decl_span: b_span, args: b_args }
+            ) if a_span == b_span => {
+                if !a_args.is_empty() && a_args.len() == b_args.len() {
+                    for (a, b) in a_args.iter().zip(b_args) {
+                        a.infer(b, params, inferred);
+                    }
+                }
+                else {
+                    for (a, b) in a.iter().zip(b) {
+                        a.ty.infer(&b.ty, params, inferred);
+                    }
+                }
+            }
+            (Ty::Alias { name: _, ty, decl_span: _ }, b) => ty.infer(b, params, inferred),
+            _ => {}
+        }
+    }
+
+    /// Give the type parameters of a generic type the types `args`, which 
+    /// must have as many items as there are parameters
+    pub fn instantiate(&self, args: &[Ty]) -> Ty {
+        let Ty::Generic { params, ty } = self else {
+            return self.clone();
+        };
+        match ty.substitute(&params.iter().cloned().zip(args.iter().cloned()).collect::<Vec<_>>()) {
+            // Instances of generic structs remember their arguments, so that 
+            // they can be substituted later and are shown like `Box<int>`
+            Ty::Struct { name, fields, decl_span, args: _ } => Ty::Struct {
+                name,
+                fields,
+                decl_span,
+                args: args.to_vec(),
+            },
+            other => other,
+        }
+    }
+
+    /// The type of a generic item without its type parameters, or this type 
+    /// if it isn't generic
+    pub fn ungeneric(self) -> Ty {
+        match self {
+            Ty::Generic { params: _, ty } => *ty,
+            other => other,
+        }
+    }
+
+    /// The name of the namespace that methods on this type are declared in. 
+    /// Methods of generic structs are declared on the struct, so instances 
+    /// like `Box<int>` share the namespace `Box`
+    pub fn method_namespace(&self) -> String {
+        match self.reduce() {
+            Ty::Struct { name, fields: _, decl_span: _, args: _ } => name.clone(),
+            Ty::Generic { params: _, ty } => ty.method_namespace(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Wrap this type in an optional, unless it's already optional
+    pub fn into_optional(self) -> Ty {
+        match self.reduce() {
+            Ty::Option { ty: _ } => self,
+            _ if self.is_unreal() => self,
+            _ => Ty::Option { ty: self.into() },
         }
     }
 
@@ -122,21 +420,45 @@ impl Display for Ty {
             Self::Int => f.write_str("int"),
             Self::Float => f.write_str("float"),
             Self::String => f.write_str("string"),
-            Self::Function { params, ret_ty } => write!(
-                f,
-                "fun({}) -> {ret_ty}", params.iter()
-                    .map(|(p, t)| if let Some(p) = p {
-                        format!("{p}: {t}")
-                    }
-                    else {
-                        t.to_string()
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ),
+            Self::Function { params, ret_ty } => fmt_function(f, &[], params, ret_ty),
+            Self::Param { name, decl_span: _ } => write!(f, "{name}"),
+            Self::Generic { params: generics, ty } => match ty.as_ref() {
+                Self::Function { params, ret_ty } => fmt_function(f, generics, params, ret_ty),
+                _ => write!(f, "{ty}<{}>", join_tys(generics)),
+            },
             Self::Option { ty } => write!(f, "{ty}?"),
+            Self::List { ty } => write!(f, "[{ty}]"),
+            Self::Map { key, value } => write!(f, "[{key}: {value}]"),
             Self::Alias { name, ty: _, decl_span: _ } => write!(f, "{name}"),
             Self::Named { name, ty: _, decl_span: _ } => write!(f, "{name}"),
+            Self::Struct { name, fields: _, decl_span: _, args } if args.is_empty() => write!(f, "{name}"),
+            Self::Struct { name, fields: _, decl_span: _, args } => write!(f, "{name}<{}>", join_tys(args)),
+            Self::Enum { name, variants: _, decl_span: _ } => write!(f, "{name}"),
         }
     }
 }
+
+fn join_tys(tys: &[Ty]) -> String {
+    tys.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn fmt_function(
+    f: &mut std::fmt::Formatter<'_>,
+    generics: &[Ty],
+    params: &[(Option<String>, Ty)],
+    ret_ty: &Ty
+) -> std::fmt::Result {
+    let generics = if generics.is_empty() { String::new() } else { format!("<{}>", join_tys(generics)) };
+    write!(
+        f,
+        "fun{generics}({}) -> {ret_ty}", params.iter()
+            .map(|(p, t)| if let Some(p) = p {
+                format!("{p}: {t}")
+            }
+            else {
+                t.to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
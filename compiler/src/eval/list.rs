@@ -0,0 +1,100 @@
+use crate::{
+    checker::{ty::Ty, entity::Entity},
+    parser::parse::NodePool,
+    shared::src::ArcSpan
+};
+use super::{Evaluator, EvalResult, Interrupt, value::Value};
+
+/// The names of the methods every list has
+pub(crate) const METHODS: &[&str] = &["map", "push"];
+
+/// Get the builtin method `name` of lists of `item`, which takes the list
+/// as `this`
+pub(crate) fn list_method(item: &Ty, name: &str) -> Option<Entity> {
+    let this = (Some(String::from("this")), Ty::List { ty: item.clone().into() });
+    let (ty, intrinsic) = match name {
+        "push" => (
+            Ty::Function {
+                params: vec![this, (Some(String::from("item")), item.clone())],
+                ret_ty: Ty::Void.into(),
+            },
+            "List::push"
+        ),
+        "map" => {
+            let res = Ty::Param { name: String::from("U"), decl_span: ArcSpan::builtin() };
+            (
+                Ty::Generic {
+                    params: vec![res.clone()],
+                    ty: Ty::Function {
+                        params: vec![this, (
+                            Some(String::from("f")),
+                            Ty::Function { params: vec![(None, item.clone())], ret_ty: res.clone().into() }
+                        )],
+                        ret_ty: Ty::List { ty: res.into() }.into(),
+                    }.into(),
+                },
+                "List::map"
+            )
+        }
+        _ => None?,
+    };
+    Some(Entity::new(ty, ArcSpan::builtin(), false).with_intrinsic(intrinsic))
+}
+
+/// Whether the list method implemented by the intrinsic `name` modifies the
+/// list it is called on
+pub(crate) fn modifies_list(name: &str) -> bool {
+    name == "List::push"
+}
+
+fn expect_list(value: Option<Value>, span: &ArcSpan) -> Result<Vec<Value>, Interrupt> {
+    match value {
+        Some(Value::List(items)) => Ok(items),
+        other => Err(Interrupt::Error(
+            format!("Expected a list, got {}", other.map(|o| o.kind()).unwrap_or("nothing".into())),
+            span.clone()
+        )),
+    }
+}
+
+/// Run the list method implemented by the intrinsic `name`, returning its
+/// result along with the list it was called on after the call
+pub(crate) fn call_list_method(
+    name: &'static str,
+    pool: &NodePool,
+    args: Vec<(Option<String>, Value)>,
+    span: ArcSpan,
+    eval: &mut Evaluator
+) -> Result<(Value, Value), Interrupt> {
+    let params = match name {
+        "List::push" => ["this", "item"],
+        _ => ["this", "f"],
+    };
+    let mut args = super::bind_args(&params.map(String::from), args).into_iter();
+    let mut items = expect_list(args.next().flatten(), &span)?;
+    match (name, args.next().flatten()) {
+        ("List::push", Some(item)) => {
+            items.push(item);
+            Ok((Value::Void, Value::List(items)))
+        }
+        ("List::map", Some(Value::Function(fun))) => {
+            let res = items.iter()
+                .map(|i| eval.call(pool, fun.clone(), vec![(None, i.clone())], span.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((Value::List(res), Value::List(items)))
+        }
+        _ => Err(Interrupt::Error(format!("Unknown compiler intrinsic {name}"), span)),
+    }
+}
+
+/// Call a list method that isn't called directly on a list, like one stored
+/// in a variable. Changes to the list are lost
+pub(crate) fn call_intrinsic(
+    name: &'static str,
+    pool: &NodePool,
+    args: Vec<(Option<String>, Value)>,
+    span: ArcSpan,
+    eval: &mut Evaluator
+) -> EvalResult {
+    call_list_method(name, pool, args, span, eval).map(|r| r.0)
+}
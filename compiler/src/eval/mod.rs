@@ -0,0 +1,247 @@
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::decl::{FunDecl, FunDeclNode, DecoratedDecl, DecoratedDeclNode, StructField, StructFieldNode},
+    checker::{resolve::ResolveNode, entity::Entity, ty::Ty},
+    parser::parse::{Node, NodePool, Ref, RefToNode, NoConstructs},
+    shared::{src::ArcSpan, logger::{LoggerRef, Message, Level, Note}}
+};
+use value::{Value, Function};
+use reflect::Code;
+
+pub mod value;
+pub mod reflect;
+pub mod list;
+
+// todo: allow customizing max call depth via a compiler option
+const MAX_CALL_DEPTH: usize = 256;
+
+/// Something that stops the evaluation of an expression before it produces
+/// a value
+#[derive(Debug)]
+pub enum Interrupt {
+    Break,
+    Continue,
+    Return(Value),
+    /// Evaluation failed, with a message and where it failed
+    Error(String, ArcSpan),
+}
+
+pub type EvalResult = Result<Value, Interrupt>;
+
+/// A Node that can be run at compile time
+pub trait EvalNode: ResolveNode {
+    /// Evaluate this node. The node must have been successfully checked first
+    fn eval_node(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult;
+}
+
+pub trait EvalRef: Ref {
+    fn eval_ref(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult;
+}
+
+impl<T: EvalNode> EvalRef for RefToNode<T> {
+    fn eval_ref(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        self.get(pool).eval_node(pool, eval)
+    }
+}
+
+impl<T: EvalRef> EvalRef for NoConstructs<T> {
+    fn eval_ref(&self, pool: &NodePool, eval: &mut Evaluator) -> EvalResult {
+        (**self).eval_ref(pool, eval)
+    }
+}
+
+/// Match arguments to the parameters they are passed to. The checker has
+/// already made sure that the arguments are valid for the parameters
+pub(crate) fn bind_args(params: &[String], args: Vec<(Option<String>, Value)>) -> Vec<Option<Value>> {
+    let mut res = vec![None; params.len()];
+    let mut positional_ix = 0usize;
+    for (name, value) in args {
+        let ix = match name {
+            Some(name) => params.iter().position(|p| *p == name),
+            None => {
+                positional_ix += 1;
+                Some(positional_ix - 1)
+            }
+        };
+        if let Some(slot) = ix.and_then(|ix| res.get_mut(ix)) {
+            *slot = Some(value);
+        }
+    }
+    res
+}
+
+/// Runs checked code at compile time, such as the bodies of macros
+pub struct Evaluator {
+    logger: LoggerRef,
+    /// Every function declaration by the span of its declaration, for
+    /// finding the function an entity refers to
+    functions: HashMap<ArcSpan, FunDecl>,
+    /// Every struct field declaration by its span, for finding the accessors 
+    /// of computed properties
+    properties: HashMap<ArcSpan, StructField>,
+    /// The locals of every function being called, with the innermost block
+    /// scope last
+    frames: Vec<Vec<HashMap<String, Value>>>,
+    /// The name and span of the decorator whose macro is being run
+    invocation: Option<(String, ArcSpan)>,
+}
+
+impl Evaluator {
+    fn new(pool: &NodePool, logger: LoggerRef) -> Self {
+        Self {
+            logger,
+            functions: pool.all_of::<FunDeclNode>().into_iter()
+                .map(|f| (f.get(pool).span_or_builtin(pool), f))
+                .collect(),
+            properties: pool.all_of::<StructFieldNode>().into_iter()
+                .map(|f| (f.get(pool).span_or_builtin(pool), f))
+                .collect(),
+            frames: vec![vec![HashMap::new()]],
+            invocation: None,
+        }
+    }
+
+    /// Run `f` in a new block scope
+    pub fn with_scope<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.frames.last_mut().unwrap().push(HashMap::new());
+        let res = f(self);
+        self.frames.last_mut().unwrap().pop();
+        res
+    }
+    /// Run the body of a function `f` in a new frame with `locals` declared.
+    /// Returning from the function produces its result
+    pub fn with_frame(
+        &mut self,
+        locals: Vec<(String, Value)>,
+        span: &ArcSpan,
+        f: impl FnOnce(&mut Self) -> EvalResult
+    ) -> EvalResult {
+        if self.frames.len() > MAX_CALL_DEPTH {
+            return Err(Interrupt::Error(
+                format!("Maximum compile time call depth reached ({MAX_CALL_DEPTH})"),
+                span.clone()
+            ));
+        }
+        self.frames.push(vec![locals.into_iter().collect()]);
+        let res = f(self);
+        self.frames.pop();
+        match res {
+            Err(Interrupt::Return(value)) => Ok(value),
+            other => other,
+        }
+    }
+    /// Declare a local in the current scope, shadowing any previous one
+    pub fn declare(&mut self, name: String, value: Value) {
+        self.frames.last_mut().unwrap().last_mut().unwrap().insert(name, value);
+    }
+    /// Assign a new value to an existing local of the function being run. 
+    /// Returns false if there's no such local
+    pub fn assign(&mut self, name: &str, value: Value) -> bool {
+        let Some(frame) = self.frames.last_mut() else { return false };
+        match frame.iter_mut().rev().find_map(|s| s.get_mut(name)) {
+            Some(local) => {
+                *local = value;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Find the declaration of the struct field declared at `span`
+    pub(crate) fn property(&self, span: &ArcSpan) -> Option<StructField> {
+        self.properties.get(span).copied()
+    }
+    /// Find the value of a local of the function being run
+    pub fn local(&self, name: &str) -> Option<Value> {
+        self.frames.last()?.iter().rev().find_map(|s| s.get(name)).cloned()
+    }
+    /// Get the value of an item that isn't a local. Only functions and unit
+    /// enum variants are known at compile time
+    pub fn entity_value(&self, name: &str, entity: Option<&Entity>, span: &ArcSpan) -> EvalResult {
+        if let Some(ent) = entity {
+            if let Some(intrinsic) = ent.intrinsic() {
+                return Ok(Value::Function(Function::Intrinsic(intrinsic)));
+            }
+            if let Some(fun) = self.functions.get(&ent.span()) {
+                return Ok(Value::Function(Function::Decl(*fun)));
+            }
+            if let Ty::Enum { name: enum_name, variants, decl_span: _ } = ent.ty().reduce() {
+                if let Some(v) = variants.iter().find(|v| v.decl_span == ent.span()) {
+                    return Ok(Value::Struct { name: format!("{enum_name}::{}", v.name), fields: vec![] });
+                }
+            }
+        }
+        Err(Interrupt::Error(format!("{name} can not be used at compile time"), span.clone()))
+    }
+    /// Call a function with arguments that have been checked to match it
+    pub fn call(
+        &mut self,
+        pool: &NodePool,
+        fun: Function,
+        mut args: Vec<(Option<String>, Value)>,
+        span: ArcSpan
+    ) -> EvalResult {
+        match fun {
+            Function::Decl(decl) => decl.get(pool).call(pool, self, args, span),
+            Function::Lambda { node, captures } => node.get(pool).call(pool, self, captures, args, span),
+            Function::Intrinsic(name) if name.starts_with("List::") => list::call_intrinsic(name, pool, args, span, self),
+            Function::Intrinsic(name) => reflect::call_intrinsic(name, pool, args, span, self),
+            Function::Bound { fun, this } => {
+                args.insert(0, (None, *this));
+                self.call(pool, *fun, args, span)
+            }
+        }
+    }
+
+    pub fn logger(&self) -> LoggerRef {
+        self.logger.clone()
+    }
+    /// The name and span of the decorator whose macro is being run
+    pub fn invocation(&self) -> (String, ArcSpan) {
+        self.invocation.clone().unwrap_or_default()
+    }
+    /// Log a message from the macro being run at the place it was invoked
+    pub fn log<S: std::fmt::Display>(&self, level: Level, msg: S) {
+        let span = self.invocation().1;
+        self.logger.lock().unwrap().log(Message::new(level, msg, span.as_ref()));
+    }
+    /// Run `f` as the invocation of the macro `name` at `span`, reporting
+    /// any error it fails with
+    pub(crate) fn invoke(&mut self, name: String, span: ArcSpan, f: impl FnOnce(&mut Self) -> EvalResult) -> Option<Value> {
+        self.invocation = Some((name.clone(), span.clone()));
+        let res = f(self);
+        self.invocation = None;
+        match res {
+            Ok(value) => Some(value),
+            Err(Interrupt::Error(msg, at)) => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Error,
+                    msg,
+                    at.as_ref()
+                ).note(Note::new_at(format!("While running macro {name} here"), span.as_ref())));
+                None
+            }
+            // The checker doesn't allow these to escape a function
+            Err(Interrupt::Break | Interrupt::Continue | Interrupt::Return(_)) => None,
+        }
+    }
+}
+
+/// Run the macros of every decorated declaration in the pool whose macros 
+/// haven't been run yet, returning the code they generated. The pool must
+/// have been checked without errors
+pub(crate) fn run_macros(pool: &NodePool, logger: LoggerRef) -> Vec<(DecoratedDecl, Code)> {
+    let mut eval = Evaluator::new(pool, logger);
+    let mut generated = vec![];
+    for decl in pool.all_of::<DecoratedDeclNode>() {
+        // Declarations in codegen templates are only run once the template 
+        // has been instantiated
+        if pool.is_template(decl.id()) || decl.get_mut(pool).mark_expanded() {
+            continue;
+        }
+        let code = decl.get(pool).run_macros(pool, &mut eval);
+        generated.extend(code.into_iter().map(|c| (decl, c)));
+    }
+    generated
+}
@@ -0,0 +1,285 @@
+
+use crate::{
+    ast::expr::{Expr, ExprNode},
+    checker::{ty::{Ty, Field, Variant}, path::{FullIdentPath, Ident}, entity::Entity},
+    parser::parse::{NodePool, Node},
+    shared::{src::ArcSpan, logger::Level}
+};
+use super::{Evaluator, EvalResult, Interrupt, value::Value};
+
+/// A list of names along with the names of their types
+type NamedTypes = &'static [(&'static str, &'static str)];
+
+/// The types in the `Reflect` namespace along with their fields. Macros
+/// receive values of these types describing the declaration they are
+/// applied to
+const TYPES: &[(&str, NamedTypes)] = &[
+    ("Struct", &[("name", "string"), ("fieldCount", "int"), ("fields", "[Field]")]),
+    ("Field", &[("name", "string"), ("typeName", "string"), ("hasDefault", "bool"), ("dependencyCount", "int")]),
+    ("Function", &[("name", "string"), ("paramCount", "int"), ("returnType", "string")]),
+    ("Variable", &[("name", "string"), ("typeName", "string"), ("hasValue", "bool")]),
+    ("Enum", &[("name", "string"), ("variantCount", "int")]),
+    ("Expr", &[("source", "string")]),
+    ("Code", &[]),
+];
+
+/// Functions implemented by the compiler, with their parameters and return
+/// type. Capitalized type names refer to the types in `TYPES`
+const INTRINSICS: &[(&str, NamedTypes, &str)] = &[
+    ("Reflect::error", &[("msg", "string")], "void"),
+    ("Reflect::warn", &[("msg", "string")], "void"),
+    ("Reflect::info", &[("msg", "string")], "void"),
+    ("Reflect::Struct::field", &[("this", "Struct"), ("index", "int")], "Field"),
+    ("Reflect::Enum::variant", &[("this", "Enum"), ("index", "int")], "string"),
+    ("Reflect::Field::dependency", &[("this", "Field"), ("index", "int")], "string"),
+    ("Reflect::Variable::value", &[("this", "Variable")], "Expr"),
+    ("Reflect::findRefs", &[("field", "Field")], "[Expr]"),
+];
+
+/// Get a builtin type by name. Capitalized names are the types of the 
+/// `Reflect` namespace, like `Struct` for `Reflect::Struct`, and may be 
+/// made a list like `[Field]`
+pub(crate) fn reflect_ty(name: &str) -> Ty {
+    if let Some(name) = name.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+        return Ty::List { ty: reflect_ty(name).into() };
+    }
+    match TYPES.iter().find(|t| t.0 == name) {
+        Some((name, fields)) => Ty::Struct {
+            name: format!("Reflect::{name}"),
+            fields: fields.iter()
+                .map(|(name, ty)| Field {
+                    name: name.to_string(),
+                    ty: reflect_ty(ty),
+                    has_default: false,
+                    accessors: None,
+                    depends: Vec::new(),
+                    decl_span: ArcSpan::builtin(),
+                })
+                .collect(),
+            decl_span: ArcSpan::builtin(),
+            args: vec![],
+        },
+        None => Ty::new_builtin(name),
+    }
+}
+
+/// Whether values of a type can fill the holes of codegen templates
+pub(crate) fn is_spliceable(ty: &Ty) -> bool {
+    match ty.reduce() {
+        Ty::Invalid | Ty::Never | Ty::Bool | Ty::Int | Ty::Float | Ty::String => true,
+        // Generated code can only be added next to declarations
+        Ty::Struct { name, fields: _, decl_span: _, args: _ } => name != "Reflect::Code" && TYPES.iter().any(|t| *name == format!("Reflect::{}", t.0)),
+        _ => false,
+    }
+}
+
+pub(crate) fn builtin_types() -> Vec<(FullIdentPath, Ty)> {
+    TYPES.iter()
+        .map(|(name, _)| (
+            FullIdentPath::new([Ident::from("Reflect"), Ident::from(*name)]),
+            reflect_ty(name)
+        ))
+        .collect()
+}
+
+pub(crate) fn builtin_entities() -> Vec<(FullIdentPath, Entity)> {
+    INTRINSICS.iter()
+        .map(|(path, params, ret_ty)| (
+            FullIdentPath::new(path.split("::").map(Ident::from).collect::<Vec<_>>()),
+            Entity::new(
+                Ty::Function {
+                    params: params.iter().map(|(n, t)| (Some(n.to_string()), reflect_ty(t))).collect(),
+                    ret_ty: reflect_ty(ret_ty).into(),
+                },
+                ArcSpan::builtin(),
+                false
+            ).with_intrinsic(path)
+        ))
+        .collect()
+}
+
+/// Code generated by a codegen block: its template along with the values 
+/// its holes were filled with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Code {
+    /// The body of the codegen block
+    pub template: ArcSpan,
+    /// The span of each hole in the template and the value it was filled with
+    pub splices: Vec<(ArcSpan, Value)>,
+    /// The name and span of the decorator whose macro generated the code
+    pub invocation: (String, ArcSpan),
+}
+
+/// A declaration or expression inspected by a macro
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reflect {
+    Struct {
+        name: String,
+        fields: Vec<Field>,
+    },
+    Field(Field),
+    Function {
+        name: String,
+        params: Vec<(Option<String>, Ty)>,
+        ret_ty: Ty,
+    },
+    Variable {
+        name: String,
+        ty: Ty,
+        value: Option<Expr>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<Variant>,
+    },
+    Expr(Expr),
+    Code(Code),
+}
+
+impl Reflect {
+    /// The name of the kind of this value in the `Reflect` namespace
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Struct { .. } => "Struct",
+            Self::Field(_) => "Field",
+            Self::Function { .. } => "Function",
+            Self::Variable { .. } => "Variable",
+            Self::Enum { .. } => "Enum",
+            Self::Expr(_) => "Expr",
+            Self::Code(_) => "Code",
+        }
+    }
+    pub fn type_name(&self) -> String {
+        format!("Reflect::{}", self.kind())
+    }
+    /// The name of the declaration this describes
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Struct { name, .. } | Self::Function { name, .. } |
+            Self::Variable { name, .. } | Self::Enum { name, .. } => Some(name),
+            Self::Field(field) => Some(&field.name),
+            Self::Expr(_) | Self::Code(_) => None,
+        }
+    }
+    /// Get the value of one of the fields listed in `TYPES`
+    pub fn member(&self, name: &str, pool: &NodePool) -> Option<Value> {
+        Some(match (self, name) {
+            (Self::Struct { name, fields: _ }, "name") => Value::String(name.clone()),
+            (Self::Struct { name: _, fields }, "fieldCount") => Value::Int(fields.len() as i64),
+            (Self::Struct { name: _, fields }, "fields") => Value::List(
+                fields.iter().map(|f| Value::Reflect(Reflect::Field(f.clone()).into())).collect()
+            ),
+            (Self::Field(field), "name") => Value::String(field.name.clone()),
+            (Self::Field(field), "typeName") => Value::String(field.ty.to_string()),
+            (Self::Field(field), "hasDefault") => Value::Bool(field.has_default),
+            (Self::Field(field), "dependencyCount") => Value::Int(field.depends.len() as i64),
+            (Self::Function { name, .. }, "name") => Value::String(name.clone()),
+            (Self::Function { params, .. }, "paramCount") => Value::Int(params.len() as i64),
+            (Self::Function { ret_ty, .. }, "returnType") => Value::String(ret_ty.to_string()),
+            (Self::Variable { name, .. }, "name") => Value::String(name.clone()),
+            (Self::Variable { ty, .. }, "typeName") => Value::String(ty.to_string()),
+            (Self::Variable { value, .. }, "hasValue") => Value::Bool(value.is_some()),
+            (Self::Enum { name, variants: _ }, "name") => Value::String(name.clone()),
+            (Self::Enum { name: _, variants }, "variantCount") => Value::Int(variants.len() as i64),
+            (Self::Expr(expr), "source") => Value::String(
+                expr.get(pool).span(pool)
+                    .map(|s| s.0.data()[s.1].to_string())
+                    .unwrap_or_default()
+            ),
+            _ => None?,
+        })
+    }
+}
+
+fn expect_reflect(value: Option<Value>, span: &ArcSpan) -> Result<Reflect, Interrupt> {
+    match value {
+        Some(Value::Reflect(r)) => Ok(*r),
+        other => Err(Interrupt::Error(
+            format!(
+                "Expected a value describing a declaration, got {}",
+                other.map(|o| o.kind()).unwrap_or("nothing".into())
+            ),
+            span.clone()
+        )),
+    }
+}
+
+fn expect_index(value: Option<Value>, len: usize, span: &ArcSpan) -> Result<usize, Interrupt> {
+    match value {
+        Some(Value::Int(i)) if i >= 0 && (i as usize) < len => Ok(i as usize),
+        Some(Value::Int(i)) => Err(Interrupt::Error(
+            format!("Index {i} is out of range, the length is {len}"),
+            span.clone()
+        )),
+        other => Err(Interrupt::Error(
+            format!("Expected an index, got {}", other.map(|o| o.kind()).unwrap_or("nothing".into())),
+            span.clone()
+        )),
+    }
+}
+
+/// Run one of the functions listed in `INTRINSICS`
+pub(crate) fn call_intrinsic(
+    name: &'static str,
+    pool: &NodePool,
+    args: Vec<(Option<String>, Value)>,
+    span: ArcSpan,
+    eval: &mut Evaluator
+) -> EvalResult {
+    let params = INTRINSICS.iter()
+        .find(|i| i.0 == name)
+        .map(|i| i.1.iter().map(|p| p.0.to_string()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let mut args = super::bind_args(&params, args).into_iter();
+    let mut arg = || args.next().flatten();
+    match name {
+        "Reflect::error" | "Reflect::warn" | "Reflect::info" => {
+            let level = match name {
+                "Reflect::error" => Level::Error,
+                "Reflect::warn" => Level::Warning,
+                _ => Level::Info,
+            };
+            eval.log(level, arg().map(|a| a.to_string()).unwrap_or_default());
+            Ok(Value::Void)
+        }
+        "Reflect::Struct::field" => match expect_reflect(arg(), &span)? {
+            Reflect::Struct { name: _, fields } => {
+                let ix = expect_index(arg(), fields.len(), &span)?;
+                Ok(Value::Reflect(Reflect::Field(fields[ix].clone()).into()))
+            }
+            other => Err(Interrupt::Error(format!("Expected Reflect::Struct, got {}", other.type_name()), span)),
+        }
+        "Reflect::Enum::variant" => match expect_reflect(arg(), &span)? {
+            Reflect::Enum { name: _, variants } => {
+                let ix = expect_index(arg(), variants.len(), &span)?;
+                Ok(Value::String(variants[ix].name.clone()))
+            }
+            other => Err(Interrupt::Error(format!("Expected Reflect::Enum, got {}", other.type_name()), span)),
+        }
+        "Reflect::Field::dependency" => match expect_reflect(arg(), &span)? {
+            Reflect::Field(field) => {
+                let ix = expect_index(arg(), field.depends.len(), &span)?;
+                Ok(Value::String(field.depends[ix].clone()))
+            }
+            other => Err(Interrupt::Error(format!("Expected Reflect::Field, got {}", other.type_name()), span)),
+        }
+        "Reflect::Variable::value" => match expect_reflect(arg(), &span)? {
+            Reflect::Variable { name: _, ty: _, value: Some(value) } => Ok(Value::Reflect(Reflect::Expr(value).into())),
+            Reflect::Variable { name, ty: _, value: None } => Err(Interrupt::Error(
+                format!("Variable {name} has no value"),
+                span
+            )),
+            other => Err(Interrupt::Error(format!("Expected Reflect::Variable, got {}", other.type_name()), span)),
+        }
+        "Reflect::findRefs" => match expect_reflect(arg(), &span)? {
+            Reflect::Field(field) => Ok(Value::List(
+                pool.all_of::<ExprNode>().into_iter()
+                    .filter(|e| matches!(*e.get(pool), ExprNode::Member(m) if m.get(pool).refers_to(&field.decl_span)))
+                    .map(|e| Value::Reflect(Reflect::Expr(e).into()))
+                    .collect()
+            )),
+            other => Err(Interrupt::Error(format!("Expected Reflect::Field, got {}", other.type_name()), span)),
+        }
+        _ => Err(Interrupt::Error(format!("Unknown compiler intrinsic {name}"), span)),
+    }
+}
@@ -0,0 +1,116 @@
+
+use std::fmt::Display;
+
+use crate::{ast::{decl::FunDecl, atom::Lambda}, checker::ty::Ty};
+use super::reflect::Reflect;
+
+/// A function that can be called at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum Function {
+    Decl(FunDecl),
+    Lambda {
+        node: Lambda,
+        /// The values of the locals the lambda captured when it was created
+        captures: Vec<(String, Value)>,
+    },
+    /// A function implemented by the compiler, such as `Reflect::error`
+    Intrinsic(&'static str),
+    /// A method along with the value it was accessed on
+    Bound {
+        fun: Box<Function>,
+        this: Box<Value>,
+    },
+}
+
+/// A value produced by running code at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Void,
+    /// The value of an optional that has no value. Optionals that do have 
+    /// one are represented by the value itself
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    /// The entries of a map in the order they were inserted
+    Map(Vec<(Value, Value)>),
+    /// An instance of a struct or an enum variant. Unit variants have no
+    /// fields
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    Function(Function),
+    Reflect(Box<Reflect>),
+}
+
+impl Value {
+    /// The name of the kind of this value for error messages
+    pub fn kind(&self) -> String {
+        match self {
+            Self::Void => "void".into(),
+            Self::None => "none".into(),
+            Self::Bool(_) => "bool".into(),
+            Self::Int(_) => "int".into(),
+            Self::Float(_) => "float".into(),
+            Self::String(_) => "string".into(),
+            Self::List(_) => "list".into(),
+            Self::Map(_) => "map".into(),
+            Self::Struct { name, fields: _ } => name.clone(),
+            Self::Function(_) => "function".into(),
+            Self::Reflect(r) => r.type_name(),
+        }
+    }
+    /// Whether this value is of the type `ty`, for `is` tests
+    pub fn is_of(&self, ty: &Ty) -> bool {
+        match (self, ty.reduce()) {
+            (Self::None, Ty::Option { ty: _ }) => true,
+            (value, Ty::Option { ty }) => value.is_of(ty),
+            (Self::Void, Ty::Void) | (Self::Bool(_), Ty::Bool) | (Self::Int(_), Ty::Int) |
+            (Self::Float(_), Ty::Float) | (Self::String(_), Ty::String) |
+            (Self::Function(_), Ty::Function { .. }) => true,
+            (Self::List(items), Ty::List { ty }) => items.iter().all(|i| i.is_of(ty)),
+            (Self::Map(entries), Ty::Map { key, value }) => {
+                entries.iter().all(|(k, v)| k.is_of(key) && v.is_of(value))
+            }
+            (Self::Struct { name, fields: _ }, Ty::Struct { name: ty_name, .. }) => name == ty_name,
+            // Enum variants are named after their variant
+            (Self::Struct { name, fields: _ }, Ty::Enum { name: ty_name, .. }) => {
+                name.strip_prefix(ty_name.as_str()).is_some_and(|v| v.starts_with("::"))
+            }
+            (Self::Reflect(r), Ty::Struct { name, .. }) => r.type_name() == *name,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Void => write!(f, "void"),
+            Self::None => write!(f, "none"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(n) => write!(f, "{n}"),
+            Self::String(s) => write!(f, "{s}"),
+            Self::List(items) => write!(
+                f, "[{}]",
+                items.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ")
+            ),
+            Self::Map(entries) if entries.is_empty() => write!(f, "[:]"),
+            Self::Map(entries) => write!(
+                f, "[{}]",
+                entries.iter().map(|(k, v)| format!("{k}: {v}")).collect::<Vec<_>>().join(", ")
+            ),
+            Self::Struct { name, fields } if fields.is_empty() => write!(f, "{name}"),
+            Self::Struct { name, fields } => write!(
+                f, "{name} {{ {} }}",
+                fields.iter().map(|(n, v)| format!("{n}: {v}")).collect::<Vec<_>>().join(", ")
+            ),
+            Self::Function(_) => write!(f, "<function>"),
+            Self::Reflect(r) => write!(f, "<{}>", r.type_name()),
+        }
+    }
+}
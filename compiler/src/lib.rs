@@ -2,7 +2,7 @@
 #![warn(clippy::todo)]
 
 use checker::coherency::Checker;
-use checker::pool::AST;
+use checker::pool::ASTPool;
 use checker::ty::Ty;
 use parser::parse::NodePool;
 use parser::tokenizer::{Tokenizer, Token};
@@ -13,11 +13,12 @@ pub mod parser;
 pub mod shared;
 pub mod ast;
 pub mod checker;
+pub mod eval;
 
 pub fn tokenize<'s, 'g: 's>(src: &'s Src, logger: LoggerRef) -> Vec<Token<'s>> {
     Tokenizer::new(src, logger).collect()
 }
 
-pub fn check_coherency(ast: &mut AST, list: &mut NodePool, logger: LoggerRef) -> Ty {
-    Checker::try_resolve(ast, list, logger)
+pub fn check_coherency(asts: &ASTPool, list: &mut NodePool, logger: LoggerRef) -> Ty {
+    Checker::try_resolve(asts, list, logger)
 }
@@ -2,7 +2,8 @@
 use std::{sync::Arc, marker::PhantomData, cell::RefCell};
 use crate::{
     shared::{src::{Src, ArcSpan}, logger::LoggerRef},
-    checker::{resolve::{ResolveRef, ResolveNode}, coherency::Checker, ty::Ty}
+    checker::{resolve::{ResolveRef, ResolveNode}, coherency::Checker, ty::Ty},
+    eval::{value::Value, reflect::Code}
 };
 use super::tokenizer::TokenIterator;
 use as_any::AsAny;
@@ -60,7 +61,7 @@ pub trait Node: AsAny {
     }
 
     fn span_or_builtin(&self, pool: &NodePool) -> ArcSpan {
-        self.span(pool).unwrap_or(ArcSpan::builtin())
+        self.span(pool).unwrap_or_default()
     }
 }
 
@@ -392,6 +393,43 @@ impl<T: ParseRef, M: CompileMessage> ResolveRef for DontExpect<T, M> {
     }
 }
 
+/// Parses `T` with construction expressions disallowed outside of 
+/// delimiters, for expressions that are directly followed by a block such as 
+/// the condition of an `if`
+#[derive(Debug)]
+pub struct NoConstructs<T: Ref>(T);
+
+impl<T: Ref> std::ops::Deref for NoConstructs<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Ref> Ref for NoConstructs<T> {
+    fn ids(&self) -> Vec<NodeID> {
+        self.0.ids()
+    }
+}
+
+impl<T: ParseRef> ParseRef for NoConstructs<T> {
+    fn parse_ref(pool: &mut NodePool, src: Arc<Src>, tokenizer: &mut TokenIterator) -> Result<Self, FatalParseError> {
+        let prev = tokenizer.set_allow_constructs(false);
+        let res = T::parse_ref(pool, src, tokenizer);
+        tokenizer.set_allow_constructs(prev);
+        Ok(Self(res?))
+    }
+    fn peek(pos: usize, tokenizer: &TokenIterator) -> bool {
+        T::peek(pos, tokenizer)
+    }
+}
+
+impl<T: ResolveRef> ResolveRef for NoConstructs<T> {
+    fn try_resolve_ref(&self, pool: &NodePool, checker: &mut Checker) -> Option<Ty> {
+        self.0.try_resolve_ref(pool, checker)
+    }
+}
+
 /// Marker trait for structs representing single tokens
 pub trait IsToken {
     fn assert_ty_is_token() {}
@@ -410,14 +448,22 @@ struct NodeData {
     ty: Option<Ty>,
     /// Whether the last call to `try_resolve_node` returned Some or None
     previous_resolve_state: bool,
+    /// Whether this Node has been tried to resolve at all. Nodes that never 
+    /// were are inside of another unresolved Node, which reports the error
+    visited: bool,
+    /// Whether this Node is part of a codegen template, in which case it is 
+    /// never checked
+    template: bool,
 }
 
 impl NodeData {
-    fn new<T: ResolveNode>(node: T) -> Self {
+    fn new<T: ResolveNode>(node: T, template: bool) -> Self {
         Self {
             node: Box::from(node),
             ty: None,
             previous_resolve_state: false,
+            visited: false,
+            template,
         }
     }
 }
@@ -429,20 +475,60 @@ impl NodeData {
 #[derive(Default)]
 pub struct NodePool {
     nodes: Vec<RefCell<NodeData>>,
+    /// Whether the Nodes being parsed are part of a codegen template
+    in_template: bool,
+    /// The generated code whose template is being parsed again, along with 
+    /// where it is added
+    instantiating: Option<(Code, ArcSpan)>,
 }
 
 #[allow(unused)]
 impl NodePool {
     /// Create a new empty pool
     pub fn new() -> Self {
-        Self { nodes: vec![] }
+        Self { nodes: vec![], in_template: false, instantiating: None }
     }
     /// Add a new Node to this pool. Returns the added node's ID
     pub fn add<N: ResolveNode>(&mut self, t: N) -> NodeID {
         let id = NodeID(self.nodes.len());
-        self.nodes.push(RefCell::from(NodeData::new(t)));
+        self.nodes.push(RefCell::from(NodeData::new(t, self.in_template)));
         id
     }
+    /// Run `f` with the Nodes it adds marked as being part of a codegen 
+    /// template or not
+    pub(crate) fn with_template<R>(&mut self, template: bool, f: impl FnOnce(&mut Self) -> R) -> R {
+        let old = std::mem::replace(&mut self.in_template, template);
+        let res = f(self);
+        self.in_template = old;
+        res
+    }
+    pub(crate) fn in_template(&self) -> bool {
+        self.in_template
+    }
+    /// Check whether a Node is part of a codegen template
+    pub(crate) fn is_template(&self, id: NodeID) -> bool {
+        self.nodes[id.0].borrow().template
+    }
+    /// Run `f` to parse the template of `code` again, filling its holes 
+    /// with the values of `code`. The code is added at `at`
+    pub(crate) fn with_instantiated<R>(&mut self, code: Code, at: ArcSpan, f: impl FnOnce(&mut Self) -> R) -> R {
+        let old = self.instantiating.replace((code, at));
+        let res = f(self);
+        self.instantiating = old;
+        res
+    }
+    /// Get the value to fill the hole of a codegen template starting at 
+    /// `offset` with, along with where the code is added, if the template 
+    /// is being instantiated
+    pub(crate) fn splice_fill(&self, offset: usize) -> Option<(Value, ArcSpan)> {
+        let (code, at) = self.instantiating.as_ref()?;
+        let (_, value) = code.splices.iter().find(|s| s.0.1.start == offset)?;
+        Some((value.clone(), at.clone()))
+    }
+    /// The ID the next added Node will have
+    pub(crate) fn next_id(&self) -> NodeID {
+        NodeID(self.nodes.len())
+    }
     fn get(&self, id: NodeID) -> std::cell::Ref<'_, dyn ResolveNode> {
         std::cell::Ref::map(
             self.nodes.get(id.0).unwrap().borrow(),
@@ -473,9 +559,23 @@ impl NodePool {
     fn get_data_mut(&self, id: NodeID) -> std::cell::RefMut<'_, NodeData> {
         self.nodes.get(id.0).unwrap().borrow_mut()
     }
+    /// Find all of the nodes of type `T` in this pool, in the order they 
+    /// were added
+    pub(crate) fn all_of<T: ResolveNode>(&self) -> Vec<RefToNode<T>> {
+        self.all_of_since(NodeID(0))
+    }
+    /// Find all of the nodes of type `T` added after `first`, including it
+    pub(crate) fn all_of_since<T: ResolveNode>(&self, first: NodeID) -> Vec<RefToNode<T>> {
+        (first.0..self.nodes.len())
+            .map(NodeID)
+            .filter(|id| self.nodes[id.0].borrow().node.as_ref().as_any().is::<T>())
+            .map(RefToNode::new_raw)
+            .collect()
+    }
     pub fn release_unresolved(&self, checker: &Checker, logger: LoggerRef) {
         for node in &self.nodes {
-            if !node.borrow().previous_resolve_state {
+            // Templates are never checked, so they are never resolved either
+            if !node.borrow().previous_resolve_state && node.borrow().visited && !node.borrow().template {
                 node.borrow().node.log_unresolved_reason(self, checker, logger.clone());
             }
         }
@@ -497,9 +597,15 @@ impl<T: ResolveNode> RefToNode<T> {
     pub fn get<'a>(&self, pool: &'a NodePool) -> std::cell::Ref<'a, T> {
         pool.get_as(self.0)
     }
+    pub(crate) fn get_mut<'a>(&self, pool: &'a NodePool) -> std::cell::RefMut<'a, T> {
+        pool.get_as_mut(self.0)
+    }
     pub fn resolved_ty(&self, pool: &NodePool) -> Option<Ty> {
         pool.get_data(self.0).ty.clone()
     }
+    pub fn id(&self) -> NodeID {
+        self.0
+    }
 }
 
 impl<T: ResolveNode> Clone for RefToNode<T> {
@@ -545,6 +651,78 @@ impl<T: ResolveNode> ResolveRef for RefToNode<T> {
             checker.mark_some_nodes_resolve_state_changed();
         }
         pool.get_data_mut(self.0).previous_resolve_state = result.is_some();
+        pool.get_data_mut(self.0).visited = true;
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dash_macros::ParseNode;
+    use crate::{
+        ast::token::punct,
+        checker::{coherency::Checker, resolve::ResolveNode, ty::Ty},
+        parser::tokenizer::{Tokenizer, TokenIterator},
+        shared::{logger::Logger, src::Src},
+    };
+    use super::{NodePool, ParseNode};
+
+    /// A node that can only be told apart by peeking both of its tokens
+    #[derive(Debug, ParseNode)]
+    struct ColonCommaNode {
+        colon: punct::Colon,
+        #[parse(peek_point)]
+        comma: punct::Comma,
+    }
+
+    #[derive(Debug, ParseNode)]
+    struct ColonNode {
+        colon: punct::Colon,
+    }
+
+    /// A node whose peek looks into a nested node past its first token
+    #[derive(Debug, ParseNode)]
+    struct AtColonNode {
+        at: punct::At,
+        #[parse(peek_point)]
+        inner: Colon,
+    }
+
+    impl ResolveNode for ColonCommaNode {
+        fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+            Some(Ty::Void)
+        }
+    }
+
+    impl ResolveNode for ColonNode {
+        fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+            Some(Ty::Void)
+        }
+    }
+
+    impl ResolveNode for AtColonNode {
+        fn try_resolve_node(&mut self, _: &NodePool, _: &mut Checker) -> Option<Ty> {
+            Some(Ty::Void)
+        }
+    }
+
+    fn peek<T: ParseNode>(code: &str) -> bool {
+        let src = Src::from_memory("test.dash", format!("{code}\n"));
+        let tokenizer: TokenIterator = Tokenizer::new(&src, Logger::default()).into();
+        T::peek(0, &tokenizer)
+    }
+
+    #[test]
+    fn multi_token_peek() {
+        assert!(peek::<ColonCommaNode>(": ,"));
+        assert!(!peek::<ColonCommaNode>(": x"));
+        assert!(!peek::<ColonCommaNode>(", :"));
+    }
+
+    #[test]
+    fn nested_peek_uses_its_position() {
+        assert!(peek::<AtColonNode>("@ :"));
+        assert!(!peek::<AtColonNode>("@ @"));
+        assert!(!peek::<AtColonNode>(": :"));
+    }
+}
@@ -25,6 +25,8 @@ pub(crate) const STRICT_KEYWORDS: &[&str] = &[
     "extern", "public", "private",
     // Types
     "typeof", "const",
+    // Reactivity
+    "depends",
     // Other
     "codegen", "compiler_intrinsic"
 ];
@@ -36,7 +38,7 @@ pub(crate) const RESERVED_KEYWORDS: &[&str] = &[
     // Visibility
     "export", "import",
     // Reactivity
-    "depends", "required",
+    "required",
     // Macros
     "reflect", "codegen",
     // Other
@@ -56,16 +58,20 @@ fn closing_paren(ch: char) -> char {
 
 trait IsTokenChar {
     fn is_op_char(&self) -> bool;
-    fn is_punct_char(&self) -> bool;
 }
 
 impl IsTokenChar for char {
     fn is_op_char(&self) -> bool {
         matches!(self, '=' | '+' | '-' | '/' | '%' | '&' | '|' | '^' | '*' | '~' | '!' | '?' | '<' | '>' | '#')
     }
-    fn is_punct_char(&self) -> bool {
-        matches!(self, ',' | ';' | '.' | ':' | '@')
-    }
+}
+
+/// A piece of an interpolated string
+pub enum InterpolatedPart<'s> {
+    /// Literal text between holes
+    Str(String),
+    /// The tokens inside a `{...}` hole
+    Hole(TokenTree<'s>),
 }
 
 pub enum TokenKind<'s> {
@@ -75,6 +81,7 @@ pub enum TokenKind<'s> {
     Int(i64),
     Float(f64),
     String(String),
+    InterpolatedString(Vec<InterpolatedPart<'s>>),
     Parentheses(TokenTree<'s>),
     Brackets(TokenTree<'s>),
     Braces(TokenTree<'s>),
@@ -96,6 +103,7 @@ impl Display for Token<'_> {
             TokenKind::Int(_) => write!(f, "integer"),
             TokenKind::Float(_) => write!(f, "float"),
             TokenKind::String(_) => write!(f, "string"),
+            TokenKind::InterpolatedString(_) => write!(f, "interpolated string"),
             TokenKind::Parentheses(_) => write!(f, "parenthesized expression"),
             TokenKind::Brackets(_) => write!(f, "bracketed expression"),
             TokenKind::Braces(_) => write!(f, "braced expression"),
@@ -131,6 +139,15 @@ impl<'s> Tokenizer<'s> {
     pub fn new(src: &'s Src, logger: LoggerRef) -> Self {
         Self { src, iter: src.iter(), logger, }
     }
+    /// Create a tokenizer that starts at the byte `offset` in `src`, for 
+    /// parsing a part of it again
+    pub fn new_at(src: &'s Src, offset: usize, logger: LoggerRef) -> Self {
+        let mut iter = src.iter();
+        for _ in src.data()[..offset].chars() {
+            iter.next();
+        }
+        Self { src, iter, logger }
+    }
     fn skip_ws(&mut self) {
         loop {
             // Ignore comments
@@ -157,6 +174,34 @@ impl<'s> Tokenizer<'s> {
     fn offset(&self) -> usize {
         self.iter.offset() - 1
     }
+    /// Parse the escape sequence after a backslash in a string literal
+    fn parse_escape(&mut self) -> char {
+        match self.iter.next() {
+            Some('n')  => '\n',
+            Some('t')  => '\t',
+            Some('0')  => '\0',
+            Some('r')  => '\r',
+            Some('\\') => '\\',
+            Some('\"') => '\"',
+            Some('\'') => '\'',
+            Some(c) => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Warning,
+                    format!("Invalid escape sequence '\\{c}'"),
+                    Span(self.src, self.offset() - 1..self.offset())
+                ));
+                c
+            }
+            None => {
+                self.logger.lock().unwrap().log(Message::new(
+                    Level::Warning,
+                    "Expected escape sequence",
+                    Span(self.src, self.offset() - 1..self.offset())
+                ));
+                '\\'
+            }
+        }
+    }
 }
 
 impl<'s> Iterator for Tokenizer<'s> {
@@ -286,31 +331,7 @@ impl<'s> Iterator for Tokenizer<'s> {
                 }
                 Some(c) => {
                     escaped.push(match c {
-                        '\\' => match self.iter.next() {
-                            Some('n')  => '\n',
-                            Some('t')  => '\t',
-                            Some('0')  => '\0',
-                            Some('r')  => '\r',
-                            Some('\\') => '\\',
-                            Some('\"') => '\"',
-                            Some('\'') => '\'',
-                            Some(c) => {
-                                self.logger.lock().unwrap().log(Message::new(
-                                    Level::Warning,
-                                    format!("Invalid escape sequence '\\{c}'"),
-                                    Span(self.src, self.offset() - 1..self.offset())
-                                ));
-                                c
-                            }
-                            None => {
-                                self.logger.lock().unwrap().log(Message::new(
-                                    Level::Warning,
-                                    "Expected escape sequence",
-                                    Span(self.src, self.offset() - 1..self.offset())
-                                ));
-                                '\\'
-                            }
-                        },
+                        '\\' => self.parse_escape(),
                         o => o
                     });
                     true
@@ -322,6 +343,59 @@ impl<'s> Iterator for Tokenizer<'s> {
             return make_token!(TokenKind::String(escaped))
         }
 
+        // Interpolated string
+        if parse!(next '$', '"') {
+            let mut parts = vec![];
+            let mut escaped = String::new();
+            loop {
+                match self.iter.next() {
+                    Some('"') => break,
+                    // Doubled braces are literal braces
+                    Some(c @ ('{' | '}')) if self.iter.peek() == Some(c) => {
+                        self.iter.next();
+                        escaped.push(c);
+                    }
+                    Some('{') => {
+                        parts.push(InterpolatedPart::Str(std::mem::take(&mut escaped)));
+                        let hole_start = self.offset();
+                        let mut items = vec![];
+                        loop {
+                            self.skip_ws();
+                            match self.iter.peek() {
+                                Some('}') => {
+                                    self.iter.next();
+                                    break;
+                                }
+                                Some(_) => {}
+                                None => return make_token!(TokenKind::Error("unclosed string literal".to_string())),
+                            }
+                            items.push(self.next().unwrap());
+                        }
+                        parts.push(InterpolatedPart::Hole(TokenTree {
+                            src: self.src,
+                            items: items.into_iter(),
+                            start_offset: hole_start,
+                            eof: self.offset() - 1..self.offset(),
+                            logger: self.logger.clone(),
+                        }));
+                    }
+                    Some('}') => {
+                        self.logger.lock().unwrap().log(Message::new(
+                            Level::Warning,
+                            "Unmatched '}' in interpolated string; use '}}' for a literal brace",
+                            Span(self.src, self.offset() - 1..self.offset())
+                        ));
+                        escaped.push('}');
+                    }
+                    Some('\\') => escaped.push(self.parse_escape()),
+                    Some(c) => escaped.push(c),
+                    None => return make_token!(TokenKind::Error("unclosed string literal".to_string())),
+                }
+            }
+            parts.push(InterpolatedPart::Str(escaped));
+            return make_token!(TokenKind::InterpolatedString(parts))
+        }
+
         // Punctuation
         if
             // Chained
@@ -330,6 +404,9 @@ impl<'s> Iterator for Tokenizer<'s> {
             parse!(next ',' | ';' | '@') ||
             // Arrows
             parse!(next '-' | '=', '>') ||
+            // `>` only combines with `=`, so that type arguments can be 
+            // closed next to each other or an optional like `Box<Box<int>>?`
+            parse!(next '>', '=') || parse!(next '>') ||
             // Operator
             parse!(next_while is_op_char)
         {
@@ -409,6 +486,7 @@ pub struct TokenIterator<'s> {
     peek: [Option<Token<'s>>; MAX_PEEK_COUNT],
     start_of_last_token: usize,
     last_was_braced: bool,
+    allow_constructs: bool,
     eof: Option<Range<usize>>,
     logger: LoggerRef,
 }
@@ -426,14 +504,29 @@ impl<'s> TokenIterator<'s> {
             src, logger, iter, peek,
             start_of_last_token: start_offset, eof,
             last_was_braced: false,
+            allow_constructs: true,
         }
     }
     pub fn peek(&self, n: usize) -> Option<&Token<'s>> {
-        self.peek[n].as_ref()
+        self.peek.get(n).and_then(Option::as_ref)
     }
     pub(crate) fn last_was_braced(&self) -> bool {
         self.last_was_braced
     }
+    /// Treat the last token as if it wasn't a Braced, for braces that don't 
+    /// end the item they are in and so don't make a semicolon unnecessary
+    pub(crate) fn forget_braced(&mut self) {
+        self.last_was_braced = false;
+    }
+    /// Whether a path followed by braces should be parsed as a construction 
+    /// expression. This is disabled in places like `if` conditions where the braces 
+    /// belong to the following block instead
+    pub(crate) fn allow_constructs(&self) -> bool {
+        self.allow_constructs
+    }
+    pub(crate) fn set_allow_constructs(&mut self, allow: bool) -> bool {
+        std::mem::replace(&mut self.allow_constructs, allow)
+    }
     fn eof_span(&self) -> Span<'s> {
         if let Some(r) = self.eof.clone() {
             Span(self.src, r)
@@ -1,6 +1,6 @@
 
 use std::{sync::{Arc, Mutex}, fmt::{Display, Write}};
-use crate::shared::src::Span;
+use crate::shared::src::{Span, ArcSpan};
 use colored::Colorize;
 
 use super::src::Underline;
@@ -112,6 +112,13 @@ impl<'s> Message<'s> {
         self.notes.push(note);
         self
     }
+    pub fn info(&self) -> &str {
+        &self.info
+    }
+    /// The text of each note of this message
+    pub fn notes(&self) -> impl Iterator<Item = &str> {
+        self.notes.iter().map(|n| n.info.as_str())
+    }
 }
 
 impl Display for Message<'_> {
@@ -146,6 +153,9 @@ pub struct Logger {
     logger: Box<dyn FnMut(Message)>,
     error_count: usize,
     warn_count: usize,
+    /// Notes added to every message, like where the code being checked was 
+    /// generated from
+    context_notes: Vec<(String, ArcSpan)>,
 }
 
 impl std::fmt::Debug for Logger {
@@ -160,6 +170,7 @@ impl Logger {
             logger: Box::from(logger),
             error_count: 0,
             warn_count: 0,
+            context_notes: vec![],
         }))
     }
     #[allow(clippy::should_implement_trait)]
@@ -172,8 +183,18 @@ impl Logger {
             Level::Warning => self.warn_count += 1,
             Level::Error => self.error_count += 1,
         }
+        let msg = self.context_notes.iter().fold(msg, |msg, (info, span)| {
+            msg.note(Note::new_at(info.clone(), span.as_ref()))
+        });
         (self.logger)(msg);
     }
+    /// Add a note to every message logged until the matching `pop_note`
+    pub fn push_note<S: Into<String>>(&mut self, info: S, span: ArcSpan) {
+        self.context_notes.push((info.into(), span));
+    }
+    pub fn pop_note(&mut self) {
+        self.context_notes.pop();
+    }
     pub fn errors(&self) -> usize {
         self.error_count
     }
@@ -1,6 +1,6 @@
 
 use std::{
-    path::PathBuf,
+    path::{PathBuf, Path},
     sync::Arc,
     fs,
     fmt::{Debug, Display},
@@ -71,15 +71,13 @@ impl<'s> Span<'s> {
         }
         else {
             let mut res = String::new();
-            let mut i = 1;
             let len = end.0 - start.0;
-            for line in lines {
+            for (i, line) in (1..).zip(lines) {
                 res.push_str(&output_line(start.0 + i, line, match i {
                     _ if i == len => 0..end.1,
                     1 => start.1..line.len(),
                     _ => 0..line.len(),
                 }));
-                i += 1;
             }
             res
         };
@@ -117,7 +115,7 @@ impl ArcSpan {
     pub fn builtin() -> Self {
         Self(Src::builtin(), 0..0)
     }
-    pub fn as_ref(&self) -> Span {
+    pub fn as_ref(&self) -> Span<'_> {
         Span(self.0.as_ref(), self.1.clone())
     }
 }
@@ -154,19 +152,30 @@ impl Src {
             path,
         }))
     }
+    /// Create a source from code that isn't read from disk, named after 
+    /// `path`
+    pub fn from_memory<P: Into<PathBuf>, S: Into<String>>(path: P, data: S) -> Arc<Self> {
+        Arc::from(Src::File { path: path.into(), data: data.into() })
+    }
     pub fn name(&self) -> String {
         match self {
             Src::Builtin => String::from("<compiler built-in>"),
             Src::File { path, data: _ } => path.to_string_lossy().to_string(),
         }
     }
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Src::Builtin => None,
+            Src::File { path, data: _ } => Some(path),
+        }
+    }
     pub fn data(&self) -> &str {
         match self {
             Src::Builtin => "",
             Src::File { path: _, data } => data.as_str(),
         }
     }
-    pub fn iter(&self) -> CharIter {
+    pub fn iter(&self) -> CharIter<'_> {
         CharIter::new(self.data())
     }
 }
@@ -209,28 +218,36 @@ impl Hash for Src {
 
 #[derive(Debug)]
 pub struct SrcPool {
+    /// The project directory. Module names are derived from the paths of 
+    /// the sources relative to it
+    root: PathBuf,
     srcs: Vec<Arc<Src>>,
 }
 
 impl SrcPool {
-    pub fn new(files: Vec<PathBuf>) -> Result<Self, String> {
+    pub fn new(root: PathBuf, files: Vec<PathBuf>) -> Result<Self, String> {
         Ok(Self {
+            root,
             srcs: files.into_iter().map(Src::from_file).collect::<Result<_, _>>()?
         })
     }
+    pub fn from_srcs(root: PathBuf, srcs: Vec<Arc<Src>>) -> Self {
+        Self { root, srcs }
+    }
     pub fn new_from_dir(dir: PathBuf) -> Result<Self, String> {
         if dir.is_file() {
-            return Self::new(vec![dir]);
+            let root = dir.parent().map(Path::to_path_buf).unwrap_or_default();
+            return Self::new(root, vec![dir]);
         }
         if !dir.exists() {
             Err("Directory does not exist".to_string())?;
         }
-        let srcs = Self::find_src_files(dir);
+        let srcs = Self::find_src_files(dir.clone());
         if srcs.is_empty() {
             Err("Directory is empty".to_string())
         }
         else {
-            Self::new(srcs)
+            Self::new(dir, srcs)
         }
     }
     fn find_src_files(dir: PathBuf) -> Vec<PathBuf> {
@@ -250,6 +267,17 @@ impl SrcPool {
         }
         res
     }
+    /// Get the module path of a source, i.e. `Std/React.dash` becomes 
+    /// `["Std", "React"]`
+    pub fn module_path(&self, src: &Src) -> Vec<String> {
+        let Some(path) = src.path() else {
+            return vec![];
+        };
+        let relative = path.strip_prefix(&self.root).unwrap_or(path).with_extension("");
+        relative.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect()
+    }
     pub fn iter(&self) -> impl Iterator<Item = Arc<Src>> + '_ {
         self.into_iter()
     }